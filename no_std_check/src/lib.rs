@@ -0,0 +1,112 @@
+//! Checks that `mcts_rs` searches without `std`, with an RNG of its own
+//! standing in for the hardware RNG of an embedded target. Build it for a
+//! target without `std`, for example with
+//! `cargo build -p no_std_check --target thumbv7em-none-eabihf`, and run its
+//! test on the host with `cargo test -p no_std_check`.
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use mcts_rs::mcts::{GameResult, GameState, MCTS};
+use rand::RngCore;
+
+/// Marsaglia's xorshift64 generator.
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // The all-zero state would only ever produce zeros.
+        XorShift(seed.max(1))
+    }
+}
+
+impl RngCore for XorShift {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Single-heap Nim: a move takes one to three objects, and whoever takes
+/// the last one wins.
+#[derive(Clone)]
+struct Nim {
+    heap: usize,
+    current_player: usize,
+}
+
+impl GameState for Nim {
+    type Action = usize;
+
+    fn get_legal_moves(&self) -> Vec<usize> {
+        (1..=self.heap.min(3)).collect()
+    }
+
+    fn make_move(&mut self, action: usize) {
+        self.heap -= action;
+        self.current_player = 1 - self.current_player;
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.heap == 0
+    }
+
+    fn get_winner(&self) -> Option<GameResult> {
+        self.is_terminal()
+            .then_some(GameResult::Win(1 - self.current_player))
+    }
+
+    fn current_player(&self) -> usize {
+        self.current_player
+    }
+
+    fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+        Box::new(self.clone())
+    }
+}
+
+/// The number of objects the engine takes from a heap of `heap`, with its
+/// search seeded from `rng`.
+pub fn best_take(heap: usize, rng: &mut XorShift) -> usize {
+    let nim = Nim {
+        heap,
+        current_player: 0,
+    };
+    let mut mcts = MCTS::builder().seed(rng.next_u64()).build(Box::new(nim));
+    mcts.get_best_move(2000)
+        .expect("a non-empty heap has moves")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_a_multiple_of_four() {
+        let mut rng = XorShift::new(7);
+        for heap in [5, 6, 7, 9] {
+            assert_eq!((heap - best_take(heap, &mut rng)) % 4, 0);
+        }
+    }
+}
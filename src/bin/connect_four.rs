@@ -0,0 +1,180 @@
+use mcts_rs::mcts::{GameResult, GameState, MCTS};
+use std::io::{self, Write};
+
+const ROWS: usize = 6;
+const COLS: usize = 7;
+
+// Line directions through a disc, as (row, column) steps: horizontal,
+// vertical and both diagonals.
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+#[derive(Clone)]
+struct ConnectFour {
+    // Row 0 is the bottom of the board.
+    board: [[i32; COLS]; ROWS],
+    heights: [usize; COLS],
+    current_player: i32,
+    moves_played: usize,
+    winner: Option<GameResult>,
+}
+
+impl ConnectFour {
+    fn new() -> Self {
+        ConnectFour {
+            board: [[0; COLS]; ROWS],
+            heights: [0; COLS],
+            current_player: 1,
+            moves_played: 0,
+            winner: None,
+        }
+    }
+
+    fn print_board(&self) {
+        for row in (0..ROWS).rev() {
+            let cells: Vec<&str> = self.board[row]
+                .iter()
+                .map(|&cell| match cell {
+                    1 => "X",
+                    -1 => "O",
+                    _ => ".",
+                })
+                .collect();
+            println!("{}", cells.join(" "));
+        }
+        let columns: Vec<String> = (0..COLS).map(|col| col.to_string()).collect();
+        println!("{}", columns.join(" "));
+        println!();
+    }
+
+    /// Counts the discs of `player` in a row from `(row, col)` along
+    /// `(dr, dc)`, not counting the starting cell.
+    fn count_from(&self, row: usize, col: usize, (dr, dc): (isize, isize), player: i32) -> usize {
+        let mut count = 0;
+        let (mut r, mut c) = (row as isize + dr, col as isize + dc);
+        while (0..ROWS as isize).contains(&r)
+            && (0..COLS as isize).contains(&c)
+            && self.board[r as usize][c as usize] == player
+        {
+            count += 1;
+            r += dr;
+            c += dc;
+        }
+        count
+    }
+
+    /// Whether the disc just dropped at `(row, col)` completes four in a row.
+    fn connects_four(&self, row: usize, col: usize) -> bool {
+        let player = self.board[row][col];
+        DIRECTIONS.iter().any(|&(dr, dc)| {
+            1 + self.count_from(row, col, (dr, dc), player)
+                + self.count_from(row, col, (-dr, -dc), player)
+                >= 4
+        })
+    }
+}
+
+impl GameState for ConnectFour {
+    // Moves are the columns discs are dropped into.
+    type Action = usize;
+
+    fn get_legal_moves(&self) -> Vec<usize> {
+        let mut moves = Vec::with_capacity(COLS);
+        self.legal_moves_into(&mut moves);
+        moves
+    }
+
+    fn legal_moves_into(&self, buf: &mut Vec<usize>) {
+        if self.winner.is_none() {
+            buf.extend((0..COLS).filter(|&col| self.heights[col] < ROWS));
+        }
+    }
+
+    fn make_move(&mut self, action: usize) {
+        let row = self.heights[action];
+        self.board[row][action] = self.current_player;
+        self.heights[action] += 1;
+        self.moves_played += 1;
+        if self.connects_four(row, action) {
+            self.winner = Some(GameResult::Win(player_id(self.current_player)));
+        } else if self.moves_played == ROWS * COLS {
+            self.winner = Some(GameResult::Draw);
+        }
+        self.current_player = -self.current_player;
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn get_winner(&self) -> Option<GameResult> {
+        self.winner
+    }
+
+    fn current_player(&self) -> usize {
+        player_id(self.current_player)
+    }
+
+    fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Maps the board's X (`1`) and O (`-1`) to players `0` and `1`.
+fn player_id(cell: i32) -> usize {
+    if cell == 1 {
+        0
+    } else {
+        1
+    }
+}
+
+fn read_human_move(game: &ConnectFour) -> usize {
+    loop {
+        print!("Enter your move (column): ");
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        let column = match input.trim().parse::<usize>() {
+            Ok(column) if column < COLS => column,
+            _ => {
+                println!("Please enter a column between 0 and {}.", COLS - 1);
+                continue;
+            }
+        };
+
+        if game.heights[column] == ROWS {
+            println!("That column is full.");
+            continue;
+        }
+        return column;
+    }
+}
+
+fn main() {
+    let mut game = ConnectFour::new();
+    let mut mcts = MCTS::new(game.box_clone());
+
+    while !game.is_terminal() {
+        game.print_board();
+
+        let action = if game.current_player == 1 {
+            read_human_move(&game)
+        } else {
+            let action = mcts
+                .get_best_move(20000)
+                .expect("the engine only searches non-terminal positions");
+            println!("Engine plays {}", action);
+            action
+        };
+        game.make_move(action);
+        mcts.advance_root(action);
+    }
+
+    game.print_board();
+    match game.get_winner() {
+        Some(GameResult::Win(0)) => println!("X wins!"),
+        Some(GameResult::Win(1)) => println!("O wins!"),
+        _ => println!("It's a draw!"),
+    }
+}
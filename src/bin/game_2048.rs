@@ -0,0 +1,246 @@
+use mcts_rs::mcts::{GameResult, GameState, MCTS};
+use rand::Rng;
+
+const SIZE: usize = 4;
+
+// Chance that a new tile is a 4 rather than a 2.
+const FOUR_PROBABILITY: f64 = 0.1;
+
+// The score that earns a reward of one half. Rewards approach 1 as the score
+// grows past it.
+const SCORE_SCALE: f64 = 2000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Move {
+    Slide(Direction),
+    // A tile of `value` appearing on the empty `cell`, which is up to chance
+    // rather than the player.
+    Spawn { cell: usize, value: u32 },
+}
+
+#[derive(Clone)]
+struct Game2048 {
+    board: [u32; SIZE * SIZE],
+    score: u32,
+    // Whether a new tile is about to appear after the last slide.
+    spawn_pending: bool,
+}
+
+impl Game2048 {
+    /// A board with two random starting tiles.
+    fn new(rng: &mut impl Rng) -> Self {
+        let mut game = Game2048 {
+            board: [0; SIZE * SIZE],
+            score: 0,
+            spawn_pending: false,
+        };
+        for _ in 0..2 {
+            let spawn = game.random_spawn(rng);
+            game.make_move(spawn);
+        }
+        game
+    }
+
+    fn print_board(&self) {
+        for row in self.board.chunks(SIZE) {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|&tile| match tile {
+                    0 => format!("{:>6}", "."),
+                    tile => format!("{:>6}", tile),
+                })
+                .collect();
+            println!("{}", cells.join(""));
+        }
+        println!("Score: {}\n", self.score);
+    }
+
+    /// The board and the points scored after sliding every tile towards
+    /// `direction`, merging each pair of equal tiles that meet once.
+    fn slid(&self, direction: Direction) -> ([u32; SIZE * SIZE], u32) {
+        let mut board = [0; SIZE * SIZE];
+        let mut points = 0;
+        for line in 0..SIZE {
+            let cells = line_cells(direction, line);
+            let mut tiles = cells
+                .iter()
+                .map(|&cell| self.board[cell])
+                .filter(|&tile| tile != 0)
+                .peekable();
+            let mut target = 0;
+            while let Some(tile) = tiles.next() {
+                let value = if tiles.peek() == Some(&tile) {
+                    tiles.next();
+                    points += tile * 2;
+                    tile * 2
+                } else {
+                    tile
+                };
+                board[cells[target]] = value;
+                target += 1;
+            }
+        }
+        (board, points)
+    }
+
+    fn can_slide(&self, direction: Direction) -> bool {
+        self.slid(direction).0 != self.board
+    }
+
+    fn max_tile(&self) -> u32 {
+        self.board.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Picks one of the chance outcomes with the given probabilities.
+    fn random_spawn(&self, rng: &mut impl Rng) -> Move {
+        let outcomes = self.chance_outcomes();
+        let total: f64 = outcomes.iter().map(|&(_, probability)| probability).sum();
+        let mut threshold = rng.gen::<f64>() * total;
+        for &(spawn, probability) in &outcomes {
+            if threshold < probability {
+                return spawn;
+            }
+            threshold -= probability;
+        }
+        outcomes.last().unwrap().0
+    }
+}
+
+/// The cells of row or column `line`, starting from the edge that the
+/// tiles slide towards in `direction`.
+fn line_cells(direction: Direction, line: usize) -> [usize; SIZE] {
+    std::array::from_fn(|i| match direction {
+        Direction::Left => line * SIZE + i,
+        Direction::Right => line * SIZE + SIZE - 1 - i,
+        Direction::Up => i * SIZE + line,
+        Direction::Down => (SIZE - 1 - i) * SIZE + line,
+    })
+}
+
+impl GameState for Game2048 {
+    type Action = Move;
+
+    fn get_legal_moves(&self) -> Vec<Move> {
+        if self.spawn_pending {
+            return Vec::new();
+        }
+        DIRECTIONS
+            .iter()
+            .filter(|&&direction| self.can_slide(direction))
+            .map(|&direction| Move::Slide(direction))
+            .collect()
+    }
+
+    fn make_move(&mut self, action: Move) {
+        match action {
+            Move::Slide(direction) => {
+                let (board, points) = self.slid(direction);
+                self.board = board;
+                self.score += points;
+                self.spawn_pending = true;
+            }
+            Move::Spawn { cell, value } => {
+                self.board[cell] = value;
+                self.spawn_pending = false;
+            }
+        }
+    }
+
+    // A slide always frees a cell, so the game only ends when no tile can
+    // move.
+    fn is_terminal(&self) -> bool {
+        !self.spawn_pending
+            && !DIRECTIONS
+                .iter()
+                .any(|&direction| self.can_slide(direction))
+    }
+
+    // There is nobody to beat; how well the game went is up to
+    // `terminal_reward`.
+    fn get_winner(&self) -> Option<GameResult> {
+        self.is_terminal().then_some(GameResult::Win(0))
+    }
+
+    fn current_player(&self) -> usize {
+        0
+    }
+
+    fn num_players(&self) -> usize {
+        1
+    }
+
+    // Maps the score into `[0, 1)`, so that the engine aims for a high score
+    // rather than just for the end of the game.
+    fn terminal_reward(&self, _result: GameResult, _player: usize) -> f64 {
+        let score = self.score as f64;
+        score / (score + SCORE_SCALE)
+    }
+
+    fn is_chance_node(&self) -> bool {
+        self.spawn_pending
+    }
+
+    fn chance_outcomes(&self) -> Vec<(Move, f64)> {
+        (0..SIZE * SIZE)
+            .filter(|&cell| self.board[cell] == 0)
+            .flat_map(|cell| {
+                [
+                    (Move::Spawn { cell, value: 2 }, 1.0 - FOUR_PROBABILITY),
+                    (Move::Spawn { cell, value: 4 }, FOUR_PROBABILITY),
+                ]
+            })
+            .collect()
+    }
+
+    fn box_clone(&self) -> Box<dyn GameState<Action = Move>> {
+        Box::new(self.clone())
+    }
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+    let mut game = Game2048::new(&mut rng);
+
+    // Rewards only differ by a few hundredths between moves, so explore
+    // less than the two-player default.
+    let mut mcts = MCTS::builder().exploration(0.25).build(game.box_clone());
+
+    while !game.is_terminal() {
+        game.print_board();
+
+        let action = mcts
+            .get_best_move(300)
+            .expect("the engine only searches non-terminal positions");
+        if let Move::Slide(direction) = action {
+            println!("Engine slides {:?}", direction);
+        }
+        game.make_move(action);
+        mcts.advance_root(action);
+
+        let spawn = game.random_spawn(&mut rng);
+        game.make_move(spawn);
+        mcts.advance_root(spawn);
+    }
+
+    game.print_board();
+    println!(
+        "Game over with a score of {} and a largest tile of {}.",
+        game.score,
+        game.max_tile()
+    );
+}
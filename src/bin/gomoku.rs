@@ -0,0 +1,205 @@
+use mcts_rs::mcts::{GameResult, GameState, MCTS};
+use std::io::{self, Write};
+
+const SIZE: usize = 15;
+
+// Moves are only considered within this many cells of an existing stone,
+// which keeps the branching factor manageable on the large board.
+const REACH: isize = 2;
+
+// Line directions through a stone, as (row, column) steps: horizontal,
+// vertical and both diagonals.
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+#[derive(Clone)]
+struct Gomoku {
+    board: [i32; SIZE * SIZE],
+    // Whether each cell is within `REACH` of a stone.
+    near: [bool; SIZE * SIZE],
+    current_player: i32,
+    moves_played: usize,
+    winner: Option<GameResult>,
+}
+
+impl Gomoku {
+    fn new() -> Self {
+        Gomoku {
+            board: [0; SIZE * SIZE],
+            near: [false; SIZE * SIZE],
+            current_player: 1,
+            moves_played: 0,
+            winner: None,
+        }
+    }
+
+    fn print_board(&self) {
+        let columns: Vec<String> = (0..SIZE).map(|col| format!("{:>3}", col)).collect();
+        println!("  {}", columns.join(""));
+        for row in 0..SIZE {
+            let cells: Vec<&str> = (0..SIZE)
+                .map(|col| match self.board[row * SIZE + col] {
+                    1 => "  X",
+                    -1 => "  O",
+                    _ => "  .",
+                })
+                .collect();
+            println!("{:>2}{}", row, cells.join(""));
+        }
+        println!();
+    }
+
+    /// Counts the stones of `player` in a row from `(row, col)` along
+    /// `(dr, dc)`, not counting the starting cell.
+    fn count_from(&self, row: usize, col: usize, (dr, dc): (isize, isize), player: i32) -> usize {
+        let mut count = 0;
+        let (mut r, mut c) = (row as isize + dr, col as isize + dc);
+        while on_board(r, c) && self.board[r as usize * SIZE + c as usize] == player {
+            count += 1;
+            r += dr;
+            c += dc;
+        }
+        count
+    }
+
+    /// Whether the stone just placed at `(row, col)` completes five in a row.
+    fn connects_five(&self, row: usize, col: usize) -> bool {
+        let player = self.board[row * SIZE + col];
+        DIRECTIONS.iter().any(|&(dr, dc)| {
+            1 + self.count_from(row, col, (dr, dc), player)
+                + self.count_from(row, col, (-dr, -dc), player)
+                >= 5
+        })
+    }
+}
+
+impl GameState for Gomoku {
+    // Moves are board indices, `row * 15 + col`.
+    type Action = usize;
+
+    fn get_legal_moves(&self) -> Vec<usize> {
+        let mut moves = Vec::new();
+        self.legal_moves_into(&mut moves);
+        moves
+    }
+
+    fn legal_moves_into(&self, buf: &mut Vec<usize>) {
+        if self.winner.is_some() {
+            return;
+        }
+        if self.moves_played == 0 {
+            buf.push(SIZE / 2 * SIZE + SIZE / 2);
+            return;
+        }
+        buf.extend((0..SIZE * SIZE).filter(|&cell| self.near[cell] && self.board[cell] == 0));
+    }
+
+    fn make_move(&mut self, action: usize) {
+        let (row, col) = (action / SIZE, action % SIZE);
+        self.board[action] = self.current_player;
+        self.moves_played += 1;
+        for dr in -REACH..=REACH {
+            for dc in -REACH..=REACH {
+                let (r, c) = (row as isize + dr, col as isize + dc);
+                if on_board(r, c) {
+                    self.near[r as usize * SIZE + c as usize] = true;
+                }
+            }
+        }
+        if self.connects_five(row, col) {
+            self.winner = Some(GameResult::Win(player_id(self.current_player)));
+        } else if self.moves_played == SIZE * SIZE {
+            self.winner = Some(GameResult::Draw);
+        }
+        self.current_player = -self.current_player;
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn get_winner(&self) -> Option<GameResult> {
+        self.winner
+    }
+
+    fn current_player(&self) -> usize {
+        player_id(self.current_player)
+    }
+
+    fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+        Box::new(self.clone())
+    }
+}
+
+fn on_board(row: isize, col: isize) -> bool {
+    (0..SIZE as isize).contains(&row) && (0..SIZE as isize).contains(&col)
+}
+
+/// Maps the board's X (`1`) and O (`-1`) to players `0` and `1`.
+fn player_id(cell: i32) -> usize {
+    if cell == 1 {
+        0
+    } else {
+        1
+    }
+}
+
+fn read_human_move(game: &Gomoku) -> usize {
+    loop {
+        print!("Enter your move (row col): ");
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        let coords: Vec<usize> = input
+            .split_whitespace()
+            .filter_map(|s| s.parse().ok())
+            .collect();
+
+        if coords.len() != 2 || coords[0] >= SIZE || coords[1] >= SIZE {
+            println!(
+                "Please enter a row and a column between 0 and {}.",
+                SIZE - 1
+            );
+            continue;
+        }
+
+        let action = coords[0] * SIZE + coords[1];
+        if game.board[action] != 0 {
+            println!("That cell is already taken.");
+            continue;
+        }
+        return action;
+    }
+}
+
+fn main() {
+    let mut game = Gomoku::new();
+    // Even near the stones there are dozens of moves, so let the engine
+    // widen its tree gradually rather than try every one of them first.
+    let mut mcts = MCTS::builder()
+        .progressive_widening(2.0, 0.5)
+        .build(game.box_clone());
+
+    while !game.is_terminal() {
+        game.print_board();
+
+        let action = if game.current_player == 1 {
+            read_human_move(&game)
+        } else {
+            let action = mcts
+                .get_best_move(5000)
+                .expect("the engine only searches non-terminal positions");
+            println!("Engine plays {} {}", action / SIZE, action % SIZE);
+            action
+        };
+        game.make_move(action);
+        mcts.advance_root(action);
+    }
+
+    game.print_board();
+    match game.get_winner() {
+        Some(GameResult::Win(0)) => println!("X wins!"),
+        Some(GameResult::Win(1)) => println!("O wins!"),
+        _ => println!("It's a draw!"),
+    }
+}
@@ -0,0 +1,128 @@
+use mcts_rs::mcts::{GameResult, GameState, MCTS};
+use std::io::{self, Write};
+
+const HEAPS: [usize; 3] = [3, 4, 5];
+
+#[derive(Clone)]
+struct Nim {
+    heaps: Vec<usize>,
+    current_player: usize,
+}
+
+impl Nim {
+    fn new() -> Self {
+        Nim {
+            heaps: HEAPS.to_vec(),
+            current_player: 0,
+        }
+    }
+
+    fn print_heaps(&self) {
+        for (heap, &size) in self.heaps.iter().enumerate() {
+            println!("{}: {}", heap, "|".repeat(size));
+        }
+        println!();
+    }
+}
+
+impl GameState for Nim {
+    // Moves are `(heap, count)`: take `count` objects from `heap`.
+    type Action = (usize, usize);
+
+    fn get_legal_moves(&self) -> Vec<(usize, usize)> {
+        self.heaps
+            .iter()
+            .enumerate()
+            .flat_map(|(heap, &size)| (1..=size).map(move |count| (heap, count)))
+            .collect()
+    }
+
+    fn make_move(&mut self, (heap, count): (usize, usize)) {
+        self.heaps[heap] -= count;
+        self.current_player = 1 - self.current_player;
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.heaps.iter().all(|&size| size == 0)
+    }
+
+    // Whoever takes the last object wins, so once the heaps are empty the
+    // winner is the player who just moved.
+    fn get_winner(&self) -> Option<GameResult> {
+        if self.is_terminal() {
+            Some(GameResult::Win(1 - self.current_player))
+        } else {
+            None
+        }
+    }
+
+    fn current_player(&self) -> usize {
+        self.current_player
+    }
+
+    fn box_clone(&self) -> Box<dyn GameState<Action = (usize, usize)>> {
+        Box::new(self.clone())
+    }
+}
+
+fn read_human_move(game: &Nim) -> (usize, usize) {
+    loop {
+        print!("Enter your move (heap count): ");
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        let numbers: Vec<usize> = input
+            .split_whitespace()
+            .filter_map(|s| s.parse().ok())
+            .collect();
+
+        if numbers.len() != 2 || numbers[0] >= game.heaps.len() {
+            println!(
+                "Please enter a heap between 0 and {} and a count.",
+                game.heaps.len() - 1
+            );
+            continue;
+        }
+
+        let (heap, count) = (numbers[0], numbers[1]);
+        if game.heaps[heap] == 0 {
+            println!("That heap is empty.");
+            continue;
+        }
+        if count == 0 || count > game.heaps[heap] {
+            println!(
+                "You can take between 1 and {} from that heap.",
+                game.heaps[heap]
+            );
+            continue;
+        }
+        return (heap, count);
+    }
+}
+
+fn main() {
+    let mut game = Nim::new();
+    let mut mcts = MCTS::new(game.box_clone());
+
+    while !game.is_terminal() {
+        game.print_heaps();
+
+        let action = if game.current_player == 0 {
+            read_human_move(&game)
+        } else {
+            let action = mcts
+                .get_best_move(10000)
+                .expect("the engine only searches non-terminal positions");
+            println!("Engine takes {} from heap {}", action.1, action.0);
+            action
+        };
+        game.make_move(action);
+        mcts.advance_root(action);
+    }
+
+    match game.get_winner() {
+        Some(GameResult::Win(0)) => println!("You win!"),
+        _ => println!("The engine wins!"),
+    }
+}
@@ -0,0 +1,233 @@
+use mcts_rs::mcts::{GameResult, GameState, MCTS};
+use std::io::{self, Write};
+
+const SIZE: usize = 8;
+
+// The move played by a player who has nowhere to place a disc.
+const PASS: usize = SIZE * SIZE;
+
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+#[derive(Clone)]
+struct Reversi {
+    board: [i32; SIZE * SIZE],
+    current_player: i32,
+}
+
+impl Reversi {
+    fn new() -> Self {
+        let mut board = [0; SIZE * SIZE];
+        board[3 * SIZE + 3] = -1;
+        board[4 * SIZE + 4] = -1;
+        board[3 * SIZE + 4] = 1;
+        board[4 * SIZE + 3] = 1;
+        Reversi {
+            board,
+            current_player: 1,
+        }
+    }
+
+    fn print_board(&self) {
+        println!(
+            "  {}",
+            (0..SIZE)
+                .map(|col| col.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        for row in 0..SIZE {
+            let cells: Vec<&str> = (0..SIZE)
+                .map(|col| match self.board[row * SIZE + col] {
+                    1 => "X",
+                    -1 => "O",
+                    _ => ".",
+                })
+                .collect();
+            println!("{} {}", row, cells.join(" "));
+        }
+        println!();
+    }
+
+    /// The cells flipped if `player` places a disc on the empty `cell`.
+    fn flips(&self, cell: usize, player: i32) -> Vec<usize> {
+        let mut flipped = Vec::new();
+        let (row, col) = ((cell / SIZE) as isize, (cell % SIZE) as isize);
+        for (dr, dc) in DIRECTIONS {
+            let mut line = Vec::new();
+            let (mut r, mut c) = (row + dr, col + dc);
+            while (0..SIZE as isize).contains(&r) && (0..SIZE as isize).contains(&c) {
+                let index = r as usize * SIZE + c as usize;
+                match self.board[index] {
+                    mark if mark == -player => line.push(index),
+                    mark if mark == player => {
+                        flipped.extend(line);
+                        break;
+                    }
+                    _ => break,
+                }
+                r += dr;
+                c += dc;
+            }
+        }
+        flipped
+    }
+
+    fn is_legal(&self, cell: usize, player: i32) -> bool {
+        self.board[cell] == 0 && !self.flips(cell, player).is_empty()
+    }
+
+    fn placements(&self, player: i32) -> Vec<usize> {
+        (0..SIZE * SIZE)
+            .filter(|&cell| self.is_legal(cell, player))
+            .collect()
+    }
+
+    fn has_placement(&self, player: i32) -> bool {
+        (0..SIZE * SIZE).any(|cell| self.is_legal(cell, player))
+    }
+
+    fn count(&self, player: i32) -> usize {
+        self.board.iter().filter(|&&cell| cell == player).count()
+    }
+}
+
+impl GameState for Reversi {
+    // Moves are board indices, `row * 8 + col`, or `PASS`.
+    type Action = usize;
+
+    fn get_legal_moves(&self) -> Vec<usize> {
+        let placements = self.placements(self.current_player);
+        if !placements.is_empty() {
+            placements
+        } else if self.has_placement(-self.current_player) {
+            vec![PASS]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn make_move(&mut self, action: usize) {
+        if action != PASS {
+            for cell in self.flips(action, self.current_player) {
+                self.board[cell] = self.current_player;
+            }
+            self.board[action] = self.current_player;
+        }
+        self.current_player = -self.current_player;
+    }
+
+    fn is_terminal(&self) -> bool {
+        !self.has_placement(self.current_player) && !self.has_placement(-self.current_player)
+    }
+
+    fn get_winner(&self) -> Option<GameResult> {
+        if !self.is_terminal() {
+            return None;
+        }
+        let (x, o) = (self.count(1), self.count(-1));
+        if x > o {
+            Some(GameResult::Win(0))
+        } else if o > x {
+            Some(GameResult::Win(1))
+        } else {
+            Some(GameResult::Draw)
+        }
+    }
+
+    fn current_player(&self) -> usize {
+        player_id(self.current_player)
+    }
+
+    // Scores by disc margin, so that the engine prefers winning big.
+    fn terminal_reward(&self, _result: GameResult, player: usize) -> f64 {
+        let mark = if player == 0 { 1 } else { -1 };
+        let (mine, theirs) = (self.count(mark) as f64, self.count(-mark) as f64);
+        0.5 + 0.5 * (mine - theirs) / (mine + theirs)
+    }
+
+    fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Maps the board's X (`1`) and O (`-1`) to players `0` and `1`.
+fn player_id(cell: i32) -> usize {
+    if cell == 1 {
+        0
+    } else {
+        1
+    }
+}
+
+fn read_human_move(game: &Reversi) -> usize {
+    loop {
+        print!("Enter your move (row col): ");
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        let coords: Vec<usize> = input
+            .split_whitespace()
+            .filter_map(|s| s.parse().ok())
+            .collect();
+
+        if coords.len() != 2 || coords[0] >= SIZE || coords[1] >= SIZE {
+            println!(
+                "Please enter a row and a column between 0 and {}.",
+                SIZE - 1
+            );
+            continue;
+        }
+
+        let action = coords[0] * SIZE + coords[1];
+        if !game.is_legal(action, game.current_player) {
+            println!("That move does not flip any discs.");
+            continue;
+        }
+        return action;
+    }
+}
+
+fn main() {
+    let mut game = Reversi::new();
+    let mut mcts = MCTS::new(game.box_clone());
+
+    while !game.is_terminal() {
+        game.print_board();
+
+        let action = if game.get_legal_moves() == [PASS] {
+            println!(
+                "{} has to pass.",
+                if game.current_player == 1 { "X" } else { "O" }
+            );
+            PASS
+        } else if game.current_player == 1 {
+            read_human_move(&game)
+        } else {
+            let action = mcts
+                .get_best_move(5000)
+                .expect("the engine only searches non-terminal positions");
+            println!("Engine plays {} {}", action / SIZE, action % SIZE);
+            action
+        };
+        game.make_move(action);
+        mcts.advance_root(action);
+    }
+
+    game.print_board();
+    println!("X {} - {} O", game.count(1), game.count(-1));
+    match game.get_winner() {
+        Some(GameResult::Win(0)) => println!("X wins!"),
+        Some(GameResult::Win(1)) => println!("O wins!"),
+        _ => println!("It's a draw!"),
+    }
+}
@@ -0,0 +1,16 @@
+use crate::mcts::GameState;
+use alloc::vec::Vec;
+
+/// Scores leaf states in place of rollouts, for example with a neural
+/// network that is cheaper to run on several positions at once. See
+/// [`MCTS::set_leaf_evaluator`](crate::mcts::MCTS::set_leaf_evaluator).
+///
+/// Like [`GameState::evaluate`], each value is the reward of its state for
+/// player `0`, and every other player is credited with the complement.
+/// Values are in `[0, 1]`, or in `[-1, 1]` under
+/// [`RewardScale::NegOneOne`](crate::mcts::RewardScale::NegOneOne).
+/// Evaluators must be [`Send`], like the engine that owns them.
+pub trait LeafEvaluator<A>: Send {
+    /// Returns one value per state, in the same order.
+    fn evaluate_batch(&mut self, states: &[&dyn GameState<Action = A>]) -> Vec<f64>;
+}
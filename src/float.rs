@@ -0,0 +1,42 @@
+//! The floating point functions that `f64` only has with `std`, provided
+//! through `libm` under the same names for `no_std` builds.
+
+pub(crate) trait Float {
+    fn ln(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn cos(self) -> Self;
+    fn floor(self) -> Self;
+    fn powf(self, exponent: Self) -> Self;
+    fn powi(self, exponent: i32) -> Self;
+}
+
+impl Float for f64 {
+    fn ln(self) -> f64 {
+        libm::log(self)
+    }
+
+    fn sqrt(self) -> f64 {
+        libm::sqrt(self)
+    }
+
+    fn exp(self) -> f64 {
+        libm::exp(self)
+    }
+
+    fn cos(self) -> f64 {
+        libm::cos(self)
+    }
+
+    fn floor(self) -> f64 {
+        libm::floor(self)
+    }
+
+    fn powf(self, exponent: f64) -> f64 {
+        libm::pow(self, exponent)
+    }
+
+    fn powi(self, exponent: i32) -> f64 {
+        libm::pow(self, f64::from(exponent))
+    }
+}
@@ -1 +1,19 @@
+//! Monte Carlo tree search for turn-based games, see [`mcts::MCTS`].
+//!
+//! The default `std` feature adds saving and loading trees with
+//! [`mcts::MCTS::save`], the timed searches, root parallelization and
+//! engines seeded from entropy. Without it the crate is `no_std` and only
+//! needs `alloc`: the search itself runs on `core`, with the floating point
+//! functions `core` lacks taken from `libm`. With no entropy to draw from,
+//! engines should then be seeded with [`mcts::MctsBuilder::seed`], for
+//! example from a hardware RNG.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod evaluator;
+#[cfg(not(any(feature = "std", test)))]
+mod float;
 pub mod mcts;
+pub mod rollout;
@@ -0,0 +1,177 @@
+use mcts_rs::mcts::{GameResult, GameState, MCTS};
+use mcts_rs::rollout::DecisiveRollout;
+use std::io::{self, Write};
+
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+#[derive(Clone)]
+struct TicTacToe {
+    board: [i32; 9],
+    current_player: i32,
+}
+
+impl TicTacToe {
+    fn new() -> Self {
+        TicTacToe {
+            board: [0; 9],
+            current_player: 1,
+        }
+    }
+
+    /// A cell that completes a line for the player with marks `player`.
+    fn completing_cell(&self, player: i32) -> Option<usize> {
+        LINES.iter().find_map(|line| {
+            let mut empty = None;
+            let mut count = 0;
+            for &cell in line {
+                match self.board[cell] {
+                    0 => empty = Some(cell),
+                    mark if mark == player => count += 1,
+                    _ => return None,
+                }
+            }
+            if count == 2 {
+                empty
+            } else {
+                None
+            }
+        })
+    }
+
+    fn print_board(&self) {
+        for row in 0..3 {
+            let cells: Vec<&str> = (0..3)
+                .map(|col| match self.board[row * 3 + col] {
+                    1 => "X",
+                    -1 => "O",
+                    _ => ".",
+                })
+                .collect();
+            println!("{}", cells.join(" "));
+        }
+        println!();
+    }
+}
+
+impl GameState for TicTacToe {
+    // Moves are board indices, `row * 3 + col`.
+    type Action = usize;
+
+    fn get_legal_moves(&self) -> Vec<usize> {
+        (0..9).filter(|&i| self.board[i] == 0).collect()
+    }
+
+    fn make_move(&mut self, action: usize) {
+        self.board[action] = self.current_player;
+        self.current_player = -self.current_player;
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.get_winner().is_some()
+    }
+
+    fn get_winner(&self) -> Option<GameResult> {
+        for line in LINES.iter() {
+            let [a, b, c] = *line;
+            if self.board[a] != 0
+                && self.board[a] == self.board[b]
+                && self.board[b] == self.board[c]
+            {
+                return Some(GameResult::Win(player_id(self.board[a])));
+            }
+        }
+        if self.board.iter().all(|&cell| cell != 0) {
+            return Some(GameResult::Draw);
+        }
+        None
+    }
+
+    fn current_player(&self) -> usize {
+        player_id(self.current_player)
+    }
+
+    fn winning_move(&self) -> Option<usize> {
+        self.completing_cell(self.current_player)
+    }
+
+    fn must_block(&self) -> Option<usize> {
+        self.completing_cell(-self.current_player)
+    }
+
+    fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Maps the board's X (`1`) and O (`-1`) to players `0` and `1`.
+fn player_id(cell: i32) -> usize {
+    if cell == 1 {
+        0
+    } else {
+        1
+    }
+}
+
+fn read_human_move(game: &TicTacToe) -> usize {
+    loop {
+        print!("Enter your move (row col): ");
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        let coords: Vec<usize> = input
+            .split_whitespace()
+            .filter_map(|s| s.parse().ok())
+            .collect();
+
+        if coords.len() != 2 || coords[0] >= 3 || coords[1] >= 3 {
+            println!("Please enter a row and a column between 0 and 2.");
+            continue;
+        }
+
+        let action = coords[0] * 3 + coords[1];
+        if game.board[action] != 0 {
+            println!("That cell is already taken.");
+            continue;
+        }
+        return action;
+    }
+}
+
+fn main() {
+    let mut game = TicTacToe::new();
+    let mut mcts = MCTS::new(game.box_clone());
+    mcts.set_rollout_policy(Box::new(DecisiveRollout));
+
+    while !game.is_terminal() {
+        game.print_board();
+
+        let action = if game.current_player == 1 {
+            read_human_move(&game)
+        } else {
+            let action = mcts
+                .get_best_move(10000)
+                .expect("the engine only searches non-terminal positions");
+            println!("Engine plays {} {}", action / 3, action % 3);
+            action
+        };
+        game.make_move(action);
+        mcts.advance_root(action);
+    }
+
+    game.print_board();
+    match game.get_winner() {
+        Some(GameResult::Win(0)) => println!("X wins!"),
+        Some(GameResult::Win(1)) => println!("O wins!"),
+        _ => println!("It's a draw!"),
+    }
+}
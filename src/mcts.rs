@@ -0,0 +1,7545 @@
+use crate::evaluator::LeafEvaluator;
+#[cfg(not(any(feature = "std", test)))]
+use crate::float::Float;
+use crate::rollout::{RandomRollout, RolloutPolicy};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::ControlFlow;
+use core::sync::atomic::{AtomicBool, Ordering};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "parallel")]
+use std::sync::mpsc;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// Emits a `tracing` event at the trace level when the `tracing` feature is
+/// enabled, and compiles to nothing otherwise.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*);
+    };
+}
+
+/// Identifies a node of the search tree. Indices are only valid until the
+/// tree is restructured by [`MCTS::advance_root`] or [`MCTS::reset`].
+pub type NodeIndex = usize;
+
+/// Errors reported by the search entry points of [`MCTS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MctsError {
+    /// The root state is not terminal but has no legal moves, or the search
+    /// was given no iterations to expand any of them.
+    NoLegalMoves,
+    /// The root state is already terminal, so there is no move to make.
+    TerminalRoot,
+    /// A rollout reached a terminal state whose `get_winner` is `None` or
+    /// names a player who is not in the game.
+    InvalidWinner(Option<GameResult>),
+    /// The move at this position of a sequence given to
+    /// [`MCTS::play_sequence`] cannot be played.
+    IllegalMove(usize),
+}
+
+impl fmt::Display for MctsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MctsError::NoLegalMoves => write!(f, "no legal moves available from the root"),
+            MctsError::TerminalRoot => write!(f, "the root state is already terminal"),
+            MctsError::InvalidWinner(winner) => write!(f, "unexpected winner {:?}", winner),
+            MctsError::IllegalMove(position) => {
+                write!(f, "move {} of the sequence is not legal", position)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MctsError {}
+
+/// How [`MCTS::get_best_move_with`] chooses among the root's children once
+/// the search is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalMoveSelection {
+    /// The child with the most visits ("robust child").
+    MostVisits,
+    /// The child with the highest `wins / visits` ("max child"). Children that
+    /// were never visited are ignored.
+    HighestValue,
+    /// The child that is both the most visited and the highest valued
+    /// ("robust-max child"), falling back to the most visited one.
+    Robust,
+}
+
+/// Statistics about a single search, returned by
+/// [`MCTS::get_best_move_report`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchReport {
+    /// Iterations that completed. Fewer than requested if the search was
+    /// stopped early, or the solver proved the root.
+    pub iterations: u32,
+    /// Nodes added to the tree by the search.
+    pub nodes_created: usize,
+    /// Average number of moves played per rollout, `0.0` without rollouts.
+    pub average_rollout_length: f64,
+    /// Depth of the tree once the search is over.
+    pub max_depth: usize,
+}
+
+/// The formula used to score children while descending the tree. What
+/// counts as `parent_visits` is chosen with [`MCTS::set_parent_visits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionPolicy {
+    /// `wins / visits + c * sqrt(ln(parent_visits) / visits)`.
+    Ucb1,
+    /// `Q + c_puct * P * sqrt(parent_visits) / (1 + visits)`, where `P` is the
+    /// prior reported by [`GameState::get_priors`].
+    Puct,
+    /// UCB1-Tuned: `wins / visits + sqrt(ln(parent_visits) / visits *
+    /// min(1/4, V))`, where `V` is an upper bound on the variance of the
+    /// child's rewards. Noisy children are explored less than under UCB1.
+    Ucb1Tuned,
+    /// EXP3: children are sampled with probability `(1 - gamma) * w /
+    /// sum(w) + gamma / k` over the `k` children, where each weight `w`
+    /// grows exponentially with the child's importance-weighted rewards.
+    /// Randomizing the choice keeps an adversary from exploiting it, so in
+    /// simultaneous-move games the visits approach a mixed equilibrium.
+    /// `gamma` is set with [`MctsBuilder::exp3_gamma`].
+    Exp3,
+}
+
+/// How the value of a node that [`MCTS`] selects by is backed up from the
+/// rewards below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupMode {
+    /// The running mean of every reward backpropagated through the node.
+    Average,
+    /// The value of the node's best child for the player choosing there,
+    /// down to the mean at the leaves and at chance nodes. Forced lines are
+    /// not diluted by the weaker replies around them, but the values are
+    /// noisier. Exact for two-player games, where one player's reward is
+    /// the other's complement.
+    Minimax,
+}
+
+/// Which count stands for `parent_visits` in the [`SelectionPolicy`]
+/// formulas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParentVisits {
+    /// The visits of the parent itself. In a plain tree a node below the
+    /// root has one more visit than its children together, from the
+    /// iteration that created it, while at the root, whose first iteration
+    /// already expands a child, the two are equal.
+    Total,
+    /// The sum of the visits of the parent's children, as in the AlphaZero
+    /// formulation of PUCT. The two differ once transpositions share
+    /// children between parents, and this keeps the exploration term
+    /// consistent with the visits of the children it is compared against.
+    ChildSum,
+}
+
+/// The range of the rewards that [`MCTS`] takes from reward functions, draw
+/// values and leaf evaluators, and of the values it reports.
+///
+/// The tree keeps its statistics on `[0, 1]` either way, so the exploration
+/// constants keep their meaning and a search picks the same moves on both
+/// scales. [`GameState::terminal_reward`] and [`GameState::evaluate`] are
+/// always on `[0, 1]`, and so are the raw wins of
+/// [`MCTS::move_statistics`] and [`MCTS::wins_visits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardScale {
+    /// A loss is `0`, a draw `0.5` and a win `1`.
+    ZeroOne,
+    /// A loss is `-1`, a draw `0` and a win `1`, as with many value
+    /// networks.
+    NegOneOne,
+}
+
+impl RewardScale {
+    /// Maps `reward` on this scale to `[0, 1]`.
+    fn normalize(self, reward: f64) -> f64 {
+        match self {
+            RewardScale::ZeroOne => reward,
+            RewardScale::NegOneOne => (reward + 1.0) / 2.0,
+        }
+    }
+
+    /// Maps `value` on `[0, 1]` to this scale.
+    fn rescale(self, value: f64) -> f64 {
+        match self {
+            RewardScale::ZeroOne => value,
+            RewardScale::NegOneOne => value * 2.0 - 1.0,
+        }
+    }
+}
+
+/// The statistics of a child that a [`MCTS::set_selection_fn`] closure
+/// scores. Wins are counted for the player choosing at the parent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChildStats {
+    pub wins: f64,
+    pub visits: f64,
+}
+
+/// The statistics of the parent whose children a
+/// [`MCTS::set_selection_fn`] closure scores. Its wins are counted for the
+/// player who moved into it, and its visits follow
+/// [`MCTS::set_parent_visits`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParentStats {
+    pub wins: f64,
+    pub visits: f64,
+}
+
+/// Scores a child during selection, see [`MCTS::set_selection_fn`].
+type SelectionFn = dyn Fn(ChildStats, ParentStats) -> f64 + Send;
+
+/// Maps a game result and a player to that player's reward, see
+/// [`MCTS::set_reward_fn`].
+type RewardFn = dyn Fn(GameResult, usize) -> f64 + Send;
+
+/// How many iterations the timed search runs between clock checks.
+const TIME_CHECK_INTERVAL: u32 = 64;
+
+/// The virtual loss that batched leaf evaluation uses unless another one is
+/// set.
+const BATCH_VIRTUAL_LOSS: f64 = 1.0;
+
+/// How a finished game ended, as reported by [`GameState::get_winner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    /// The player with this id won.
+    Win(usize),
+    Draw,
+}
+
+impl GameResult {
+    /// The reward of this result for `player`: `1.0` for the winner, `0.0`
+    /// for the other players and `0.5` for everyone on a draw.
+    pub fn reward_for(self, player: usize) -> f64 {
+        match self {
+            GameResult::Win(winner) if winner == player => 1.0,
+            GameResult::Win(_) => 0.0,
+            GameResult::Draw => 0.5,
+        }
+    }
+}
+
+/// A game that can be searched by [`MCTS`].
+///
+/// `Action` describes a single move; games with board-indexed moves can
+/// simply use `usize`, while others can use a dedicated move type.
+///
+/// Players are numbered from `0` to `num_players() - 1`. `current_player`
+/// reports who is to move, and `get_winner` reports the [`GameResult`] once
+/// the game is over. `is_terminal` must be true whenever there are no legal
+/// moves; a rollout that reaches a non-terminal state without moves scores
+/// it as a draw.
+///
+/// States must be [`Send`] so that an engine can be moved to another thread.
+pub trait GameState: Send {
+    type Action: Copy + Eq;
+
+    fn get_legal_moves(&self) -> Vec<Self::Action>;
+
+    /// Appends the legal moves to `buf`. Rollouts call this with a reused
+    /// buffer, so games can override it to avoid allocating on every move;
+    /// the default copies [`GameState::get_legal_moves`].
+    fn legal_moves_into(&self, buf: &mut Vec<Self::Action>) {
+        buf.extend(self.get_legal_moves());
+    }
+
+    fn make_move(&mut self, action: Self::Action);
+    fn is_terminal(&self) -> bool;
+    fn get_winner(&self) -> Option<GameResult>;
+    fn current_player(&self) -> usize;
+
+    /// Copies the state into a new box. Types that implement [`Clone`] can
+    /// return `Box::new(self.clone())`.
+    fn box_clone(&self) -> Box<dyn GameState<Action = Self::Action>>;
+
+    /// The state reached by playing `action`, leaving `self` untouched. Used
+    /// to create child nodes; games with persistent or structurally shared
+    /// states can override it to avoid a full copy. The default clones the
+    /// state with [`GameState::box_clone`] and calls `make_move`.
+    fn apply(&self, action: Self::Action) -> Box<dyn GameState<Action = Self::Action>> {
+        let mut state = self.box_clone();
+        state.make_move(action);
+        state
+    }
+
+    /// Takes back `action`, the last move made on this state, and returns
+    /// `true`. Games that can undo moves cheaply let the engine replay
+    /// several rollouts from one copy of a leaf, see
+    /// [`MCTS::set_rollouts_per_leaf`], instead of cloning it for each. The
+    /// default cannot undo, and returns `false` without changing the state.
+    fn undo_move(&mut self, action: Self::Action) -> bool {
+        let _ = action;
+        false
+    }
+
+    /// Number of players taking part in the game. Defaults to two.
+    fn num_players(&self) -> usize {
+        2
+    }
+
+    /// Prior probabilities of the legal moves, used by
+    /// [`SelectionPolicy::Puct`]. Defaults to a uniform distribution.
+    fn get_priors(&self) -> Vec<(Self::Action, f64)> {
+        let legal_moves = self.get_legal_moves();
+        let prior = 1.0 / legal_moves.len() as f64;
+        legal_moves
+            .into_iter()
+            .map(|action| (action, prior))
+            .collect()
+    }
+
+    /// Sorts `moves`, the legal moves of a new node, so that the most
+    /// promising come last, and returns `true`; the node then expands its
+    /// moves from the back of the list, best first. Trying captures or
+    /// central moves early helps searches with few iterations. The default
+    /// leaves the moves alone and returns `false`, which expands them in
+    /// random order. Under [`SelectionPolicy::Puct`] the priors decide the
+    /// order instead.
+    fn order_moves(&self, moves: &mut Vec<Self::Action>) -> bool {
+        let _ = moves;
+        false
+    }
+
+    /// A hash identifying the position, used to share a single node between
+    /// move orders that transpose into the same state. Returning `None`, the
+    /// default, disables the transposition table.
+    fn state_key(&self) -> Option<u64> {
+        None
+    }
+
+    /// Whether `other` is the same position as this state. Checked before
+    /// two states with equal [`GameState::state_key`]s share a node, so that
+    /// a hash collision cannot merge different positions. The default
+    /// compares the states' [`GameState::serialize_state`] encodings, and
+    /// returns `false` if either state cannot be serialized, so games that
+    /// set a key without serializing must override it.
+    fn same_state(&self, other: &dyn GameState<Action = Self::Action>) -> bool {
+        match (self.serialize_state(), other.serialize_state()) {
+            (Some(mine), Some(theirs)) => mine == theirs,
+            _ => false,
+        }
+    }
+
+    /// Encodes the state so that a tree can be saved with [`MCTS::save`].
+    /// Returning `None`, the default, makes saving fail.
+    fn serialize_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Heuristic value of a non-terminal state for player `0`, in `[0, 1]`;
+    /// every other player is credited with the complement. Used when a rollout is cut off by
+    /// [`MCTS::set_max_rollout_depth`]; defaults to an even `0.5`.
+    fn evaluate(&self) -> f64 {
+        0.5
+    }
+
+    /// Reward of a terminal state for `player`, in `[0, 1]`, given the
+    /// `result` its [`GameState::get_winner`] reported. Games with scores can
+    /// override this to reward wide margins more than narrow ones. The
+    /// default is [`GameResult::reward_for`].
+    ///
+    /// An engine's [`MCTS::set_reward_fn`] replaces this for every result,
+    /// and its [`MCTS::set_draw_value`] for draws.
+    fn terminal_reward(&self, result: GameResult, player: usize) -> f64 {
+        result.reward_for(player)
+    }
+
+    /// A move that wins the game on the spot for the player to move, if the
+    /// game can find one cheaply. Used by
+    /// [`DecisiveRollout`](crate::rollout::DecisiveRollout); defaults to
+    /// `None`.
+    fn winning_move(&self) -> Option<Self::Action> {
+        None
+    }
+
+    /// A move the player to move must play to stop an opponent from winning
+    /// on their next turn, if the game can find one cheaply. Used by
+    /// [`DecisiveRollout`](crate::rollout::DecisiveRollout); defaults to
+    /// `None`.
+    fn must_block(&self) -> Option<Self::Action> {
+        None
+    }
+
+    /// Domain estimate of how good this state is for the player who just
+    /// moved, used as a progressive bias when enabled with
+    /// [`MCTS::set_progressive_bias`]. It is called every time the state's
+    /// node is scored, so it should be cheap. Defaults to `0.0`, no bias.
+    fn heuristic_value(&self) -> f64 {
+        0.0
+    }
+
+    /// Whether the next move is made by chance, such as a dice roll, rather
+    /// than by a player. Defaults to `false`.
+    fn is_chance_node(&self) -> bool {
+        false
+    }
+
+    /// The possible outcomes of a chance node and their probabilities, which
+    /// need not be normalized. The search samples outcomes in proportion to
+    /// them instead of selecting one, so the value of a chance node is the
+    /// expectation over its outcomes. Only called when
+    /// [`GameState::is_chance_node`] is true; defaults to no outcomes.
+    fn chance_outcomes(&self) -> Vec<(Self::Action, f64)> {
+        Vec::new()
+    }
+
+    /// Samples a concrete state that the player to move cannot tell apart
+    /// from this one, filling in hidden information such as the opponents'
+    /// cards at random. Used by [`MCTS::get_best_move_determinized`]; the
+    /// default suits perfect-information games and returns a copy.
+    fn determinize(&self, rng: &mut dyn RngCore) -> Box<dyn GameState<Action = Self::Action>> {
+        let _ = rng;
+        self.box_clone()
+    }
+}
+
+/// Lets boxed states be cloned like any other value.
+impl<A: Copy + Eq> Clone for Box<dyn GameState<Action = A>> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// How a rollout ended.
+#[derive(Debug, Clone)]
+enum Outcome {
+    /// The terminal reward of every player, indexed by player.
+    Rewards(Vec<f64>),
+    /// A heuristic score for player `0`, in `[0, 1]`.
+    Score(f64),
+}
+
+impl Outcome {
+    fn reward_for(&self, player: usize) -> f64 {
+        match *self {
+            Outcome::Rewards(ref rewards) => rewards[player],
+            Outcome::Score(score) if player == 0 => score,
+            Outcome::Score(score) => 1.0 - score,
+        }
+    }
+
+    /// Pulls every reward towards `0.5` by `factor`.
+    fn discounted(self, factor: f64) -> Outcome {
+        let discount = |reward: f64| 0.5 + (reward - 0.5) * factor;
+        match self {
+            Outcome::Rewards(rewards) => {
+                Outcome::Rewards(rewards.into_iter().map(discount).collect())
+            }
+            Outcome::Score(score) => Outcome::Score(discount(score)),
+        }
+    }
+}
+
+/// A rollout played on a worker thread of [`MCTS::get_best_move_parallel`],
+/// with the state it ended in, whether the depth limit cut it off or it ran
+/// out of moves before the end, its length and, if RAVE is enabled, the
+/// moves played and the players who made them.
+#[cfg(feature = "parallel")]
+struct Playout<A> {
+    state: Box<dyn GameState<Action = A>>,
+    cut_off: bool,
+    stuck: bool,
+    depth: usize,
+    moves: Vec<(usize, A)>,
+}
+
+/// A state to roll out, the seed of the rollout's RNG, and the position of
+/// the rollout among those of its leaf.
+#[cfg(feature = "parallel")]
+type RolloutJob<A> = (usize, Box<dyn GameState<Action = A>>, u64);
+
+/// The channels to the threads of [`MCTS::get_best_move_parallel`], one job
+/// sender per thread and a receiver shared by their results.
+#[cfg(feature = "parallel")]
+struct RolloutWorkers<A> {
+    jobs: Vec<mpsc::Sender<RolloutJob<A>>>,
+    results: mpsc::Receiver<(usize, Playout<A>)>,
+}
+
+struct Node<A> {
+    // Never modified once the node is created, so whether it is terminal
+    // and who won can be worked out once.
+    state: Box<dyn GameState<Action = A>>,
+    terminal: bool,
+    winner: Option<GameResult>,
+    parent: Option<NodeIndex>,
+    children: Vec<NodeIndex>,
+    // Sum of the squared rewards, for the variance estimate of UCB1-Tuned.
+    sum_sq: f64,
+    // All-moves-as-first statistics, `(action, wins, visits)` for the moves
+    // the player to move here went on to play later in an iteration.
+    amaf: Vec<(A, f64, f64)>,
+    untried_actions: Vec<A>,
+    // Whether `GameState::order_moves` sorted `untried_actions`, best last.
+    ordered: bool,
+    last_action: Option<A>,
+    // The node's wins are counted from the point of view of this player, so
+    // that the parent picks the child that is best for whoever made
+    // `last_action`.
+    player_just_moved: usize,
+    prior: f64,
+    // Priors of this node's moves, fetched the first time it is expanded
+    // under PUCT.
+    priors: Option<Vec<(A, f64)>>,
+    // Dirichlet noise mixed into this node's score while it is a child of
+    // the root, see `MCTS::add_root_noise`.
+    noise: Option<f64>,
+    // The outcomes and their probabilities if this is a chance node.
+    chance: Option<Vec<(A, f64)>>,
+    // Sum of the rewards divided by the probability of choosing this node,
+    // and that probability the last time EXP3 sampled it.
+    exp3_gain: f64,
+    exp3_probability: f64,
+    // The node's value for `player_just_moved` under `BackupMode::Minimax`.
+    minimax: f64,
+    // The result of the game under best play from here, once the solver has
+    // proven it. Terminal nodes start out proven.
+    proven: Option<GameResult>,
+}
+
+// Written out because a derived impl would only require `A: Clone`, which is
+// not enough to clone the boxed state.
+impl<A: Copy + Eq> Clone for Node<A> {
+    fn clone(&self) -> Self {
+        Node {
+            state: self.state.clone(),
+            terminal: self.terminal,
+            winner: self.winner,
+            parent: self.parent,
+            children: self.children.clone(),
+            sum_sq: self.sum_sq,
+            amaf: self.amaf.clone(),
+            untried_actions: self.untried_actions.clone(),
+            ordered: self.ordered,
+            last_action: self.last_action,
+            player_just_moved: self.player_just_moved,
+            prior: self.prior,
+            priors: self.priors.clone(),
+            noise: self.noise,
+            chance: self.chance.clone(),
+            exp3_gain: self.exp3_gain,
+            exp3_probability: self.exp3_probability,
+            minimax: self.minimax,
+            proven: self.proven,
+        }
+    }
+}
+
+impl<A: Copy + Eq> Node<A> {
+    fn new(
+        state: Box<dyn GameState<Action = A>>,
+        parent: Option<NodeIndex>,
+        last_action: Option<A>,
+        player_just_moved: usize,
+    ) -> Self {
+        let terminal = state.is_terminal();
+        let winner = if terminal { state.get_winner() } else { None };
+        let chance = (!terminal && state.is_chance_node()).then(|| state.chance_outcomes());
+        let mut ordered = false;
+        // Games may still report moves once they are over, but there is
+        // nothing to search below a terminal node.
+        let untried_actions = match &chance {
+            _ if terminal => Vec::new(),
+            Some(outcomes) => outcomes.iter().map(|&(action, _)| action).collect(),
+            None => {
+                let mut moves = state.get_legal_moves();
+                ordered = state.order_moves(&mut moves);
+                moves
+            }
+        };
+        Node {
+            state,
+            terminal,
+            winner,
+            parent,
+            children: Vec::new(),
+            sum_sq: 0.0,
+            amaf: Vec::new(),
+            untried_actions,
+            ordered,
+            last_action,
+            player_just_moved,
+            prior: 1.0,
+            priors: None,
+            noise: None,
+            chance,
+            exp3_gain: 0.0,
+            exp3_probability: 1.0,
+            minimax: 0.0,
+            proven: winner,
+        }
+    }
+}
+
+/// A Monte Carlo tree search over games whose moves are of type `A`.
+///
+/// With the `tracing` feature enabled, every iteration runs in an
+/// `iteration` span and reports the selected, expanded and simulated nodes
+/// and each backpropagated reward as trace-level events.
+pub struct MCTS<A> {
+    nodes: Vec<Node<A>>,
+    // Total reward and visit count of each node, indexed like `nodes`. They
+    // are kept apart from the nodes so that scoring the children of a node
+    // reads contiguous memory.
+    wins: Vec<f64>,
+    visits: Vec<f64>,
+    root: NodeIndex,
+    exploration_constant: f64,
+    rng: StdRng,
+    selection_policy: SelectionPolicy,
+    c_puct: f64,
+    exp3_gamma: f64,
+    rollout_policy: Box<dyn RolloutPolicy<A>>,
+    max_rollout_depth: Option<usize>,
+    transpositions: BTreeMap<u64, NodeIndex>,
+    // The nodes visited by the current iteration, from the root down. With
+    // transpositions a node can have several parents, so backpropagation
+    // follows this path rather than `parent` links.
+    path: Vec<NodeIndex>,
+    virtual_loss: f64,
+    noise_epsilon: f64,
+    // Progressive widening coefficient and exponent, if enabled.
+    widening: Option<(f64, f64)>,
+    max_nodes: Option<usize>,
+    discount: f64,
+    progressive_bias: f64,
+    expand_all: bool,
+    early_stop: bool,
+    solver: bool,
+    // RAVE equivalence parameter, if enabled.
+    rave: Option<f64>,
+    // Replaces `GameState::terminal_reward` when set.
+    reward_fn: Option<Box<RewardFn>>,
+    // Replaces the formula of `selection_policy` when set.
+    selection_fn: Option<Box<SelectionFn>>,
+    // Replaces `GameState::terminal_reward` for draws when set. On `[0, 1]`,
+    // whatever the reward scale.
+    draw_value: Option<f64>,
+    reward_scale: RewardScale,
+    rollouts_per_leaf: usize,
+    backup: BackupMode,
+    parent_visits: ParentVisits,
+    // Replaces rollouts when set, scoring up to `batch_size` leaves at once.
+    leaf_evaluator: Option<Box<dyn LeafEvaluator<A>>>,
+    batch_size: usize,
+    // The moves of the current rollout and the players who made them, kept
+    // for the AMAF update when RAVE is enabled.
+    rollout_moves: Vec<(usize, A)>,
+    // Buffer for the legal moves of rollout states, kept between rollouts.
+    legal_moves: Vec<A>,
+    // Every move of the current rollout, chance outcomes included, so that
+    // they can be undone.
+    rollout_actions: Vec<A>,
+    // The outcome sampled at the chance node where selection stopped, for
+    // `expand` to create.
+    pending_chance: Option<A>,
+    // Leaves scored so far and the rollout moves played, for diagnostics.
+    rollouts: u64,
+    rollout_plies: u64,
+    // Nodes kept by `advance_root` so far.
+    nodes_reused: u64,
+    // Roll out new leaves while `get_best_move_parallel` is running.
+    #[cfg(feature = "parallel")]
+    workers: Option<RolloutWorkers<A>>,
+}
+
+const DEFAULT_EXPLORATION: f64 = core::f64::consts::SQRT_2;
+const DEFAULT_C_PUCT: f64 = 1.0;
+const DEFAULT_EXP3_GAMMA: f64 = 0.1;
+#[cfg(feature = "std")]
+const SAVE_MAGIC: &[u8; 4] = b"MCTS";
+#[cfg(feature = "std")]
+const SAVE_VERSION: u64 = 2;
+
+/// Configures and builds an [`MCTS`] engine. Every option defaults to the
+/// behaviour of [`MCTS::new`].
+pub struct MctsBuilder<A> {
+    exploration_constant: f64,
+    seed: Option<u64>,
+    selection_policy: SelectionPolicy,
+    c_puct: f64,
+    exp3_gamma: f64,
+    rollout_policy: Option<Box<dyn RolloutPolicy<A>>>,
+    max_rollout_depth: Option<usize>,
+    virtual_loss: f64,
+    widening: Option<(f64, f64)>,
+    max_nodes: Option<usize>,
+    discount: f64,
+    progressive_bias: f64,
+    expand_all: bool,
+    early_stop: bool,
+    solver: bool,
+    rave: Option<f64>,
+    capacity: usize,
+    reward_fn: Option<Box<RewardFn>>,
+    selection_fn: Option<Box<SelectionFn>>,
+    // On the reward scale.
+    draw_value: Option<f64>,
+    reward_scale: RewardScale,
+    rollouts_per_leaf: usize,
+    backup: BackupMode,
+    parent_visits: ParentVisits,
+    leaf_evaluator: Option<Box<dyn LeafEvaluator<A>>>,
+    batch_size: usize,
+}
+
+impl<A> Default for MctsBuilder<A> {
+    fn default() -> Self {
+        MctsBuilder {
+            exploration_constant: DEFAULT_EXPLORATION,
+            seed: None,
+            selection_policy: SelectionPolicy::Ucb1,
+            c_puct: DEFAULT_C_PUCT,
+            exp3_gamma: DEFAULT_EXP3_GAMMA,
+            rollout_policy: None,
+            max_rollout_depth: None,
+            virtual_loss: 0.0,
+            widening: None,
+            max_nodes: None,
+            discount: 1.0,
+            progressive_bias: 0.0,
+            expand_all: false,
+            early_stop: false,
+            solver: false,
+            rave: None,
+            capacity: 0,
+            reward_fn: None,
+            selection_fn: None,
+            draw_value: None,
+            reward_scale: RewardScale::ZeroOne,
+            rollouts_per_leaf: 1,
+            backup: BackupMode::Average,
+            parent_visits: ParentVisits::Total,
+            leaf_evaluator: None,
+            batch_size: 1,
+        }
+    }
+}
+
+impl<A: Copy + Eq> MctsBuilder<A> {
+    /// Creates a builder with every option at its default.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the weight of the UCT exploration term, `sqrt(2)` by default.
+    pub fn exploration(mut self, exploration_constant: f64) -> Self {
+        self.exploration_constant = exploration_constant;
+        self
+    }
+
+    /// Seeds the engine RNG so that searches are reproducible. Without a
+    /// seed the RNG is seeded from entropy, or with `0` when the `std`
+    /// feature is off.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// See [`MCTS::set_selection_policy`].
+    pub fn selection_policy(mut self, policy: SelectionPolicy) -> Self {
+        self.selection_policy = policy;
+        self
+    }
+
+    /// See [`MCTS::set_selection_fn`].
+    pub fn selection_fn(
+        mut self,
+        selection_fn: impl Fn(ChildStats, ParentStats) -> f64 + Send + 'static,
+    ) -> Self {
+        self.selection_fn = Some(Box::new(selection_fn));
+        self
+    }
+
+    /// Sets the weight of the PUCT exploration term, `1.0` by default.
+    pub fn c_puct(mut self, c_puct: f64) -> Self {
+        self.c_puct = c_puct;
+        self
+    }
+
+    /// Sets the share of uniform exploration under
+    /// [`SelectionPolicy::Exp3`], between `0` and `1`, `0.1` by default.
+    pub fn exp3_gamma(mut self, gamma: f64) -> Self {
+        self.exp3_gamma = gamma.clamp(f64::MIN_POSITIVE, 1.0);
+        self
+    }
+
+    /// See [`MCTS::set_rollout_policy`].
+    pub fn rollout_policy(mut self, policy: Box<dyn RolloutPolicy<A>>) -> Self {
+        self.rollout_policy = Some(policy);
+        self
+    }
+
+    /// See [`MCTS::set_max_rollout_depth`].
+    pub fn max_rollout_depth(mut self, depth: usize) -> Self {
+        self.max_rollout_depth = Some(depth);
+        self
+    }
+
+    /// See [`MCTS::set_virtual_loss`].
+    pub fn virtual_loss(mut self, virtual_loss: f64) -> Self {
+        self.virtual_loss = virtual_loss;
+        self
+    }
+
+    /// See [`MCTS::set_progressive_widening`].
+    pub fn progressive_widening(mut self, c: f64, alpha: f64) -> Self {
+        self.widening = Some((c, alpha));
+        self
+    }
+
+    /// See [`MCTS::set_max_nodes`].
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// See [`MCTS::set_discount`].
+    pub fn discount(mut self, discount: f64) -> Self {
+        self.discount = discount;
+        self
+    }
+
+    /// See [`MCTS::set_progressive_bias`].
+    pub fn progressive_bias(mut self, weight: f64) -> Self {
+        self.progressive_bias = weight;
+        self
+    }
+
+    /// See [`MCTS::set_expand_all`].
+    pub fn expand_all(mut self, expand_all: bool) -> Self {
+        self.expand_all = expand_all;
+        self
+    }
+
+    /// See [`MCTS::set_solver`].
+    pub fn solver(mut self, solver: bool) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// See [`MCTS::set_early_stop`].
+    pub fn early_stop(mut self, early_stop: bool) -> Self {
+        self.early_stop = early_stop;
+        self
+    }
+
+    /// See [`MCTS::with_rave`].
+    pub fn rave(mut self, equivalence: f64) -> Self {
+        self.rave = Some(equivalence);
+        self
+    }
+
+    /// See [`MCTS::set_reward_fn`].
+    pub fn reward_fn(
+        mut self,
+        reward_fn: impl Fn(GameResult, usize) -> f64 + Send + 'static,
+    ) -> Self {
+        self.reward_fn = Some(Box::new(reward_fn));
+        self
+    }
+
+    /// See [`MCTS::set_draw_value`].
+    pub fn draw_value(mut self, draw_value: f64) -> Self {
+        self.draw_value = Some(draw_value);
+        self
+    }
+
+    /// See [`MCTS::set_reward_scale`].
+    pub fn reward_scale(mut self, scale: RewardScale) -> Self {
+        self.reward_scale = scale;
+        self
+    }
+
+    /// See [`MCTS::set_rollouts_per_leaf`].
+    pub fn rollouts_per_leaf(mut self, rollouts: usize) -> Self {
+        self.rollouts_per_leaf = rollouts.max(1);
+        self
+    }
+
+    /// See [`MCTS::set_backup_mode`].
+    pub fn backup_mode(mut self, mode: BackupMode) -> Self {
+        self.backup = mode;
+        self
+    }
+
+    /// See [`MCTS::set_parent_visits`].
+    pub fn parent_visits(mut self, source: ParentVisits) -> Self {
+        self.parent_visits = source;
+        self
+    }
+
+    /// See [`MCTS::set_leaf_evaluator`].
+    pub fn leaf_evaluator(
+        mut self,
+        evaluator: Box<dyn LeafEvaluator<A>>,
+        batch_size: usize,
+    ) -> Self {
+        self.leaf_evaluator = Some(evaluator);
+        self.batch_size = batch_size.max(1);
+        if self.batch_size > 1 && self.virtual_loss == 0.0 {
+            self.virtual_loss = BATCH_VIRTUAL_LOSS;
+        }
+        self
+    }
+
+    /// See [`MCTS::with_capacity`].
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Builds an engine rooted at `state`.
+    pub fn build(self, state: Box<dyn GameState<Action = A>>) -> MCTS<A> {
+        let rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            #[cfg(feature = "std")]
+            None => StdRng::from_entropy(),
+            // There is no entropy source to draw from without `std`.
+            #[cfg(not(feature = "std"))]
+            None => StdRng::seed_from_u64(0),
+        };
+        let num_players = state.num_players();
+        let player_just_moved = (state.current_player() + num_players - 1) % num_players;
+        let mut transpositions = BTreeMap::new();
+        if let Some(key) = state.state_key() {
+            transpositions.insert(key, 0);
+        }
+        let capacity = self.capacity.max(1);
+        let mut mcts = MCTS {
+            nodes: Vec::with_capacity(capacity),
+            wins: Vec::with_capacity(capacity),
+            visits: Vec::with_capacity(capacity),
+            root: 0,
+            exploration_constant: self.exploration_constant,
+            rng,
+            selection_policy: self.selection_policy,
+            c_puct: self.c_puct,
+            exp3_gamma: self.exp3_gamma,
+            rollout_policy: self
+                .rollout_policy
+                .unwrap_or_else(|| Box::new(RandomRollout)),
+            max_rollout_depth: self.max_rollout_depth,
+            transpositions,
+            path: Vec::new(),
+            virtual_loss: self.virtual_loss,
+            noise_epsilon: 0.0,
+            widening: self.widening,
+            max_nodes: self.max_nodes,
+            discount: self.discount,
+            progressive_bias: self.progressive_bias,
+            expand_all: self.expand_all,
+            early_stop: self.early_stop,
+            solver: self.solver,
+            rave: self.rave,
+            reward_fn: self.reward_fn,
+            selection_fn: self.selection_fn,
+            draw_value: self
+                .draw_value
+                .map(|draw_value| self.reward_scale.normalize(draw_value)),
+            reward_scale: self.reward_scale,
+            rollouts_per_leaf: self.rollouts_per_leaf,
+            backup: self.backup,
+            parent_visits: self.parent_visits,
+            leaf_evaluator: self.leaf_evaluator,
+            batch_size: self.batch_size,
+            rollout_moves: Vec::new(),
+            legal_moves: Vec::new(),
+            rollout_actions: Vec::new(),
+            pending_chance: None,
+            rollouts: 0,
+            rollout_plies: 0,
+            nodes_reused: 0,
+            #[cfg(feature = "parallel")]
+            workers: None,
+        };
+        mcts.push_node(Node::new(state, None, None, player_just_moved));
+        mcts
+    }
+}
+
+impl<A: Copy + Eq> MCTS<A> {
+    /// Creates an engine using the standard UCT exploration constant `sqrt(2)`.
+    pub fn new(state: Box<dyn GameState<Action = A>>) -> Self {
+        MctsBuilder::new().build(state)
+    }
+
+    /// Returns a builder for configuring an engine before it is created.
+    pub fn builder() -> MctsBuilder<A> {
+        MctsBuilder::new()
+    }
+
+    /// Creates an engine whose UCT formula uses `exploration_constant` as the
+    /// weight of the exploration term.
+    pub fn with_exploration(
+        state: Box<dyn GameState<Action = A>>,
+        exploration_constant: f64,
+    ) -> Self {
+        MctsBuilder::new()
+            .exploration(exploration_constant)
+            .build(state)
+    }
+
+    /// Creates an engine whose rollouts are driven by an RNG seeded with
+    /// `seed`, so that searches are reproducible.
+    pub fn with_seed(state: Box<dyn GameState<Action = A>>, seed: u64) -> Self {
+        MctsBuilder::new().seed(seed).build(state)
+    }
+
+    /// Creates an engine with room for `capacity` nodes, so that searches
+    /// growing the tree to fewer nodes than that never reallocate it.
+    pub fn with_capacity(state: Box<dyn GameState<Action = A>>, capacity: usize) -> Self {
+        MctsBuilder::new().capacity(capacity).build(state)
+    }
+
+    /// Creates an engine that selects children with PUCT, weighting the
+    /// prior-driven exploration term by `c_puct`.
+    pub fn with_puct(state: Box<dyn GameState<Action = A>>, c_puct: f64) -> Self {
+        MctsBuilder::new()
+            .selection_policy(SelectionPolicy::Puct)
+            .c_puct(c_puct)
+            .build(state)
+    }
+
+    /// Creates an engine that blends each child's value with its RAVE
+    /// (all-moves-as-first) estimate, which shares credit between moves that
+    /// are good whenever they are played. The AMAF value is weighted by
+    /// `sqrt(k / (3 * visits + k))`, so `equivalence` is roughly the number
+    /// of visits at which both estimates count equally.
+    pub fn with_rave(state: Box<dyn GameState<Action = A>>, equivalence: f64) -> Self {
+        MctsBuilder::new().rave(equivalence).build(state)
+    }
+
+    /// Changes the formula used to score children during selection.
+    pub fn set_selection_policy(&mut self, policy: SelectionPolicy) {
+        self.selection_policy = policy;
+    }
+
+    /// Scores children during selection with `selection_fn(child, parent)`
+    /// in place of the formula of the [`SelectionPolicy`], and descends into
+    /// the highest scoring child, for experimenting with other formulas.
+    /// Children that were never visited are still tried first, and RAVE,
+    /// progressive bias and root noise are still mixed into the score. EXP3
+    /// samples children rather than scoring them, so it ignores the closure.
+    /// Without one, selection follows the policy, UCB1 by default.
+    pub fn set_selection_fn(
+        &mut self,
+        selection_fn: impl Fn(ChildStats, ParentStats) -> f64 + Send + 'static,
+    ) {
+        self.selection_fn = Some(Box::new(selection_fn));
+    }
+
+    /// Stops rollouts after `depth` moves and scores the reached state with
+    /// [`GameState::evaluate`]. `None` plays every rollout to the end.
+    pub fn set_max_rollout_depth(&mut self, depth: Option<usize>) {
+        self.max_rollout_depth = depth;
+    }
+
+    /// Sets the virtual loss added to the visits of every node on the path
+    /// while an iteration is in flight, which steers concurrent descents
+    /// away from each other. `0.0`, the default without a batching leaf
+    /// evaluator, disables it.
+    pub fn set_virtual_loss(&mut self, virtual_loss: f64) {
+        self.virtual_loss = virtual_loss;
+    }
+
+    /// Enables progressive widening: a node only gains a new child while its
+    /// child count is below `floor(c * (visits + 1)^alpha)`, and descends
+    /// into its existing children otherwise. This keeps the search focused in
+    /// games with large branching factors. Every node may always have at
+    /// least one child. Disabled by default, which expands every move of a
+    /// node before descending.
+    pub fn set_progressive_widening(&mut self, c: f64, alpha: f64) {
+        self.widening = Some((c, alpha));
+    }
+
+    /// Limits the tree to `max_nodes` nodes. Once the limit is reached the
+    /// search stops expanding and keeps refining the statistics of the
+    /// existing tree, running rollouts from its leaves. Searches still
+    /// return a move, but a small cap reduces their quality. `None`, the
+    /// default, lets the tree grow without bound.
+    pub fn set_max_nodes(&mut self, max_nodes: Option<usize>) {
+        self.max_nodes = max_nodes;
+    }
+
+    /// Discounts every result by `discount` per ply between the root and the
+    /// end of the rollout, pulling rewards towards a draw. The engine then
+    /// prefers quick wins and slow losses. `1.0`, the default, disables it.
+    pub fn set_discount(&mut self, discount: f64) {
+        self.discount = discount;
+    }
+
+    /// Adds `weight * h / (visits + 1)` to every child's selection score,
+    /// where `h` is the child state's [`GameState::heuristic_value`]. This
+    /// steers the early search towards moves the heuristic likes, and fades
+    /// as the moves are visited. `0.0`, the default, disables it.
+    pub fn set_progressive_bias(&mut self, weight: f64) {
+        self.progressive_bias = weight;
+    }
+
+    /// With `expand_all` set, expanding a node creates a child for every
+    /// untried action at once and rolls out from one of them, which suits
+    /// evaluators that score all children of a node together. Progressive
+    /// widening and the node cap still limit how many children are created.
+    /// Off by default.
+    pub fn set_expand_all(&mut self, expand_all: bool) {
+        self.expand_all = expand_all;
+    }
+
+    /// With `early_stop` set, iteration-bounded searches end as soon as the
+    /// most visited root move leads the runner-up by more visits than there
+    /// are iterations left, since the remaining ones could not change the
+    /// move returned. The lead is checked every 64 iterations. Off by default.
+    pub fn set_early_stop(&mut self, early_stop: bool) {
+        self.early_stop = early_stop;
+    }
+
+    /// With `solver` set, the engine proves game results as in MCTS-Solver:
+    /// a node is proven won for the player to move once one of its children
+    /// is, and proven otherwise once all its moves are expanded and proven.
+    /// Proven children are no longer selected, searches stop once the root
+    /// is proven, and a proven win is picked over the most visited move.
+    /// See [`MCTS::proven_result`].
+    ///
+    /// Results are taken from [`GameState::get_winner`], so margins scored
+    /// by `terminal_reward` are ignored by proofs. With more than two
+    /// players, a node whose moves all lose is only proven when they all
+    /// lose to the same player. Off by default.
+    pub fn set_solver(&mut self, solver: bool) {
+        self.solver = solver;
+    }
+
+    /// Scores terminal states with `reward_fn(result, player)` instead of
+    /// [`GameState::terminal_reward`], for example to value draws below an
+    /// even share. Rewards are on the [`MCTS::set_reward_scale`] and clamped
+    /// to its range.
+    pub fn set_reward_fn(&mut self, reward_fn: impl Fn(GameResult, usize) -> f64 + Send + 'static) {
+        self.reward_fn = Some(Box::new(reward_fn));
+    }
+
+    /// Sets the reward every player receives for a draw, on the current
+    /// [`MCTS::set_reward_scale`]. Lower values make the engine avoid draws,
+    /// higher ones seek them. It also scores rollouts that get stuck without
+    /// legal moves.
+    ///
+    /// Until this is called, draws are scored by
+    /// [`GameState::terminal_reward`] and stuck rollouts as the middle of
+    /// the scale. Once it is, the draw value takes precedence over
+    /// `terminal_reward` for drawn games, while a custom
+    /// [`MCTS::set_reward_fn`] takes precedence over both.
+    pub fn set_draw_value(&mut self, draw_value: f64) {
+        self.draw_value = Some(self.reward_scale.normalize(draw_value));
+    }
+
+    /// Chooses the range of the rewards the engine is given and reports,
+    /// [`RewardScale::ZeroOne`] by default. A draw value that was already
+    /// set keeps its meaning.
+    pub fn set_reward_scale(&mut self, scale: RewardScale) {
+        self.reward_scale = scale;
+    }
+
+    /// Plays `rollouts` rollouts from each new leaf, `1` by default, and
+    /// backpropagates their average reward. More rollouts give steadier
+    /// values in noisy games at the cost of a slower growing tree. Terminal
+    /// leaves and leaves scored by a leaf evaluator are still scored once,
+    /// and [`MCTS::get_best_move_parallel`] plays one rollout per thread
+    /// instead.
+    pub fn set_rollouts_per_leaf(&mut self, rollouts: usize) {
+        self.rollouts_per_leaf = rollouts.max(1);
+    }
+
+    /// Chooses how selection values children, [`BackupMode::Average`] by
+    /// default. Visit counts and the statistics reported by
+    /// [`MCTS::move_statistics`] accumulate the same either way.
+    pub fn set_backup_mode(&mut self, mode: BackupMode) {
+        if mode == BackupMode::Minimax && self.backup != mode {
+            self.seed_minimax();
+        }
+        self.backup = mode;
+    }
+
+    /// Chooses the count that selection takes as the parent's visits,
+    /// [`ParentVisits::Total`] by default.
+    pub fn set_parent_visits(&mut self, source: ParentVisits) {
+        self.parent_visits = source;
+    }
+
+    /// Starts the minimax value of every visited node from its mean, for
+    /// trees whose minimax values were not kept up to date.
+    fn seed_minimax(&mut self) {
+        for (index, node) in self.nodes.iter_mut().enumerate() {
+            if self.visits[index] > 0.0 {
+                node.minimax = self.wins[index] / self.visits[index];
+            }
+        }
+    }
+
+    /// Scores new leaves with `evaluator` instead of rolling them out.
+    /// Iteration-bounded searches gather up to `batch_size` leaves before
+    /// evaluating them with a single call, then backpropagate them all;
+    /// timed and cancellable searches evaluate one leaf at a time.
+    ///
+    /// Leaves of a batch are selected before any of them is backpropagated,
+    /// so a `batch_size` above one turns on a virtual loss of `1.0`, unless
+    /// [`MCTS::set_virtual_loss`] already set one, to spread them over
+    /// different branches. Terminal leaves are still scored from the game
+    /// result.
+    pub fn set_leaf_evaluator(&mut self, evaluator: Box<dyn LeafEvaluator<A>>, batch_size: usize) {
+        self.leaf_evaluator = Some(evaluator);
+        self.batch_size = batch_size.max(1);
+        if self.batch_size > 1 && self.virtual_loss == 0.0 {
+            self.virtual_loss = BATCH_VIRTUAL_LOSS;
+        }
+    }
+
+    /// Replaces the policy used to pick moves during rollouts. Players can
+    /// be given policies of their own with
+    /// [`PerPlayerRollout`](crate::rollout::PerPlayerRollout).
+    pub fn set_rollout_policy(&mut self, policy: Box<dyn RolloutPolicy<A>>) {
+        self.rollout_policy = policy;
+    }
+
+    fn uct_value(&self, parent_index: NodeIndex, child_index: NodeIndex) -> f64 {
+        // The UCB formulas divide by the child's visits, so an unvisited
+        // child is tried before any other. PUCT scores it from its prior.
+        let visits = self.visits[child_index];
+        if visits == 0.0
+            && (self.selection_fn.is_some() || self.selection_policy != SelectionPolicy::Puct)
+        {
+            return f64::INFINITY;
+        }
+        let child = &self.nodes[child_index];
+        let mut score = self.policy_value(parent_index, child_index);
+        if let Some(k) = self.rave {
+            let amaf = self.nodes[parent_index]
+                .amaf
+                .iter()
+                .find(|&&(action, _, _)| Some(action) == child.last_action);
+            let amaf = amaf.filter(|&&(_, _, amaf_visits)| amaf_visits > 0.0);
+            if let Some(&(_, amaf_wins, amaf_visits)) = amaf {
+                let beta = (k / (3.0 * visits + k)).sqrt();
+                score = beta * amaf_wins / amaf_visits + (1.0 - beta) * score;
+            }
+        }
+        if self.progressive_bias != 0.0 {
+            score += self.progressive_bias * child.state.heuristic_value() / (visits + 1.0);
+        }
+        score
+    }
+
+    fn policy_value(&self, parent_index: NodeIndex, child_index: NodeIndex) -> f64 {
+        let child = &self.nodes[child_index];
+        let parent_visits = match self.parent_visits {
+            ParentVisits::Total => self.visits[parent_index],
+            ParentVisits::ChildSum => self.nodes[parent_index]
+                .children
+                .iter()
+                .map(|&sibling| self.visits[sibling])
+                .sum(),
+        };
+        let (wins, visits) = (self.wins[child_index], self.visits[child_index]);
+        let noise = child.noise.filter(|_| parent_index == self.root);
+        let value = match self.backup {
+            BackupMode::Average => wins / visits,
+            BackupMode::Minimax => child.minimax,
+        };
+        let score = match (&self.selection_fn, self.selection_policy) {
+            (Some(selection_fn), _) => {
+                let child_stats = ChildStats { wins, visits };
+                let parent_stats = ParentStats {
+                    wins: self.wins[parent_index],
+                    visits: parent_visits,
+                };
+                selection_fn(child_stats, parent_stats)
+            }
+            (None, SelectionPolicy::Ucb1) => {
+                value + self.exploration_constant * (parent_visits.ln() / visits).sqrt()
+            }
+            (None, SelectionPolicy::Ucb1Tuned) => {
+                let mean = wins / visits;
+                let log_ratio = parent_visits.ln() / visits;
+                let variance = child.sum_sq / visits - mean * mean + (2.0 * log_ratio).sqrt();
+                value + (log_ratio * variance.min(0.25)).sqrt()
+            }
+            // EXP3 samples children instead of scoring them, see
+            // `sample_exp3_child`.
+            (None, SelectionPolicy::Exp3) => wins / visits,
+            (None, SelectionPolicy::Puct) => {
+                let q = if visits > 0.0 { value } else { 0.0 };
+                let prior = match noise {
+                    Some(noise) => {
+                        (1.0 - self.noise_epsilon) * child.prior + self.noise_epsilon * noise
+                    }
+                    None => child.prior,
+                };
+                return q + self.c_puct * prior * parent_visits.sqrt() / (1.0 + visits);
+            }
+        };
+        match noise {
+            Some(noise) => (1.0 - self.noise_epsilon) * score + self.noise_epsilon * noise,
+            None => score,
+        }
+    }
+
+    fn select(&mut self, node_index: NodeIndex) -> NodeIndex {
+        self.path.clear();
+        let mut current = node_index;
+        loop {
+            self.enter(current);
+            let node = &self.nodes[current];
+            if let Some(outcomes) = &node.chance {
+                let Some(action) = sample_outcome(outcomes, &mut self.rng) else {
+                    return current;
+                };
+                let child = node
+                    .children
+                    .iter()
+                    .copied()
+                    .find(|&child| self.nodes[child].last_action == Some(action));
+                match child {
+                    Some(child) => {
+                        current = child;
+                        continue;
+                    }
+                    None => {
+                        self.pending_chance = Some(action);
+                        return current;
+                    }
+                }
+            }
+            if self.can_expand(current) || node.children.is_empty() {
+                return current;
+            }
+            if self.selection_policy == SelectionPolicy::Exp3 {
+                current = self.sample_exp3_child(current);
+                continue;
+            }
+
+            let children = node.children.iter().copied();
+            // Solved children need no more search, unless they are all
+            // that is left.
+            let open = children
+                .clone()
+                .filter(|&child| !self.solver || self.nodes[child].proven.is_none());
+            current = self
+                .best_uct_child(current, open)
+                .or_else(|| self.best_uct_child(current, children))
+                .unwrap();
+        }
+    }
+
+    /// Picks the child of `parent_index` among `children` with the highest
+    /// selection score. Ties go to the child created first, so that the
+    /// choice only depends on the tree.
+    fn best_uct_child(
+        &self,
+        parent_index: NodeIndex,
+        children: impl Iterator<Item = NodeIndex>,
+    ) -> Option<NodeIndex> {
+        children.max_by(|&a, &b| {
+            compare_values(
+                self.uct_value(parent_index, a),
+                self.uct_value(parent_index, b),
+            )
+            .then(b.cmp(&a))
+        })
+    }
+
+    /// Samples a child of `parent_index` from the EXP3 distribution over its
+    /// children, or over its unsolved children under the solver, and records
+    /// the probability it was chosen with for backpropagation.
+    fn sample_exp3_child(&mut self, parent_index: NodeIndex) -> NodeIndex {
+        let children = &self.nodes[parent_index].children;
+        let mut candidates: Vec<NodeIndex> = children
+            .iter()
+            .copied()
+            .filter(|&child| !self.solver || self.nodes[child].proven.is_none())
+            .collect();
+        if candidates.is_empty() {
+            candidates.clone_from(children);
+        }
+        let gamma = self.exp3_gamma;
+        let k = candidates.len() as f64;
+        // Weights are `exp(gamma / k * gain)`, taken relative to the largest
+        // gain so that they cannot overflow.
+        let max_gain = candidates
+            .iter()
+            .map(|&child| self.nodes[child].exp3_gain)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<(NodeIndex, f64)> = candidates
+            .iter()
+            .map(|&child| {
+                let gain = self.nodes[child].exp3_gain - max_gain;
+                (child, (gamma / k * gain).exp())
+            })
+            .collect();
+        let total: f64 = weights.iter().map(|&(_, weight)| weight).sum();
+        let probabilities: Vec<(NodeIndex, f64)> = weights
+            .into_iter()
+            .map(|(child, weight)| (child, (1.0 - gamma) * weight / total + gamma / k))
+            .collect();
+        let child = sample_outcome(&probabilities, &mut self.rng).unwrap();
+        self.nodes[child].exp3_probability = probabilities
+            .iter()
+            .find(|&&(candidate, _)| candidate == child)
+            .map_or(1.0, |&(_, probability)| probability);
+        child
+    }
+
+    /// Adds a child to `node_index`, the node that selection stopped at, and
+    /// returns it. Returns `None` if `node_index` is a leaf that cannot grow:
+    /// a terminal node, a node without moves, or any leaf once the tree is
+    /// full. Selection only stops early at nodes that can grow, so there is
+    /// never a deeper node to fall back on.
+    fn expand(&mut self, node_index: NodeIndex) -> Option<NodeIndex> {
+        // Chance nodes grow the outcome that selection sampled, unless the
+        // tree is full and the chance node is rolled out from instead.
+        if let Some(action) = self.pending_chance.take() {
+            if self.is_full() {
+                return None;
+            }
+            self.nodes[node_index]
+                .untried_actions
+                .retain(|&untried| untried != action);
+            let child = self.add_child(node_index, action);
+            self.enter(child);
+            return Some(child);
+        }
+        if self.selection_policy == SelectionPolicy::Puct {
+            self.load_priors(node_index);
+        }
+
+        if !self.can_expand(node_index) {
+            return None;
+        }
+
+        let action = self.take_untried_action(node_index);
+        let child = self.add_child(node_index, action);
+        if self.expand_all {
+            while self.can_expand(node_index) {
+                let action = self.take_untried_action(node_index);
+                self.add_child(node_index, action);
+            }
+        }
+        self.enter(child);
+        Some(child)
+    }
+
+    /// Links the node reached by playing `action` from `node_index` as one
+    /// of its children, creating it unless a transposition already holds it.
+    fn add_child(&mut self, node_index: NodeIndex, action: A) -> NodeIndex {
+        debug_assert!(
+            match &self.nodes[node_index].chance {
+                Some(outcomes) => outcomes.iter().any(|&(outcome, _)| outcome == action),
+                None => self.nodes[node_index]
+                    .state
+                    .get_legal_moves()
+                    .contains(&action),
+            },
+            "expand tried an action that get_legal_moves no longer reports"
+        );
+        let parent_state = &self.nodes[node_index].state;
+        let mover = parent_state.current_player();
+        let new_state = parent_state.apply(action);
+
+        let key = new_state.state_key();
+        let existing = key.and_then(|key| self.find_transposition(key, action, new_state.as_ref()));
+        if let Some(existing) = existing {
+            self.nodes[node_index].children.push(existing);
+            return existing;
+        }
+
+        let mut new_node = Node::new(new_state, Some(node_index), Some(action), mover);
+        if let Some(priors) = &self.nodes[node_index].priors {
+            new_node.prior = prior_of(priors, action);
+        }
+        let new_index = self.push_node(new_node);
+        self.nodes[node_index].children.push(new_index);
+        if let Some(key) = key {
+            self.transpositions.entry(key).or_insert(new_index);
+        }
+        new_index
+    }
+
+    /// Appends `node` to the tree with no visits yet and returns its index.
+    fn push_node(&mut self, node: Node<A>) -> NodeIndex {
+        self.nodes.push(node);
+        self.wins.push(0.0);
+        self.visits.push(0.0);
+        self.nodes.len() - 1
+    }
+
+    /// Removes the next action to expand from the non-empty untried actions
+    /// of `node_index`. With priors loaded that is the most likely move, and
+    /// with moves sorted by [`GameState::order_moves`] the most promising
+    /// one; otherwise it is drawn at random, so that the order of
+    /// [`GameState::get_legal_moves`] does not bias the early search.
+    fn take_untried_action(&mut self, node_index: NodeIndex) -> A {
+        let node = &mut self.nodes[node_index];
+        if node.priors.is_some() || node.ordered {
+            return node.untried_actions.pop().unwrap();
+        }
+        let index = self.rng.gen_range(0..node.untried_actions.len());
+        node.untried_actions.swap_remove(index)
+    }
+
+    /// Whether `node_index` has untried actions and, under progressive
+    /// widening, room for another child.
+    fn can_expand(&self, node_index: NodeIndex) -> bool {
+        let node = &self.nodes[node_index];
+        if node.untried_actions.is_empty() || self.is_full() {
+            return false;
+        }
+        match self.widening {
+            Some((c, alpha)) => {
+                let allowed = (c * (self.visits[node_index] + 1.0).powf(alpha))
+                    .floor()
+                    .max(1.0);
+                (node.children.len() as f64) < allowed
+            }
+            None => true,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.max_nodes
+            .is_some_and(|max_nodes| self.nodes.len() >= max_nodes)
+    }
+
+    /// Looks up an existing node for `state`, whose key is `key`. The node is
+    /// only shared if it holds the same position, if it was reached by the
+    /// same `action`, so that `last_action` stays valid for every parent,
+    /// and if it is not an ancestor on the current path, which would turn
+    /// the tree into a cycle. A state whose key collides with another
+    /// position's gets a node of its own.
+    fn find_transposition(
+        &self,
+        key: u64,
+        action: A,
+        state: &dyn GameState<Action = A>,
+    ) -> Option<NodeIndex> {
+        let &existing = self.transpositions.get(&key)?;
+        let node = &self.nodes[existing];
+        if node.last_action == Some(action)
+            && !self.path.contains(&existing)
+            && node.state.same_state(state)
+        {
+            Some(existing)
+        } else {
+            None
+        }
+    }
+
+    /// Fetches the priors of `node_index` the first time it is expanded and
+    /// orders its untried actions so that the most likely move is tried first.
+    fn load_priors(&mut self, node_index: NodeIndex) {
+        let node = &mut self.nodes[node_index];
+        if node.priors.is_some() {
+            return;
+        }
+
+        let priors = node.state.get_priors();
+        sort_by_prior(&mut node.untried_actions, &priors);
+        node.priors = Some(priors);
+    }
+
+    fn simulate(&mut self, node_index: NodeIndex) -> Result<Outcome, MctsError> {
+        let plies_from_root = self.path.len() - 1;
+        let node = &self.nodes[node_index];
+        if node.terminal {
+            let outcome = self.terminal_outcome(node.state.as_ref(), node.winner)?;
+            return Ok(self.finish_rollout(outcome, 0, plies_from_root));
+        }
+        let state = node.state.box_clone();
+        self.simulate_from(state, plies_from_root)
+    }
+
+    /// Scores the non-terminal `start` with the leaf evaluator, or with
+    /// [`MCTS::set_rollouts_per_leaf`] rollouts averaged together.
+    fn simulate_from(
+        &mut self,
+        start: Box<dyn GameState<Action = A>>,
+        plies_from_root: usize,
+    ) -> Result<Outcome, MctsError> {
+        if let Some(evaluator) = &mut self.leaf_evaluator {
+            let value = evaluator.evaluate_batch(&[start.as_ref()])[0];
+            self.rollout_moves.clear();
+            let outcome = Outcome::Score(self.reward_scale.normalize(value).clamp(0.0, 1.0));
+            return Ok(self.finish_rollout(outcome, 0, plies_from_root));
+        }
+        #[cfg(feature = "parallel")]
+        if self.workers.is_some() {
+            return self.parallel_rollouts(start, plies_from_root);
+        }
+        if self.rollouts_per_leaf == 1 {
+            let mut state = start;
+            return self.rollout(state.as_mut(), plies_from_root);
+        }
+        let mut state = start.box_clone();
+        let mut totals = vec![0.0; state.num_players()];
+        for rollout in 0..self.rollouts_per_leaf {
+            if rollout > 0 && !self.undo_rollout(state.as_mut()) {
+                state = start.box_clone();
+            }
+            let outcome = self.rollout(state.as_mut(), plies_from_root)?;
+            for (player, total) in totals.iter_mut().enumerate() {
+                *total += outcome.reward_for(player);
+            }
+        }
+        let rollouts = self.rollouts_per_leaf as f64;
+        Ok(Outcome::Rewards(
+            totals.into_iter().map(|total| total / rollouts).collect(),
+        ))
+    }
+
+    /// Plays `state`, reached `plies_from_root` moves below the root, out
+    /// with the rollout policy and scores the result.
+    fn rollout(
+        &mut self,
+        state: &mut dyn GameState<Action = A>,
+        plies_from_root: usize,
+    ) -> Result<Outcome, MctsError> {
+        let mut depth = 0;
+        self.rollout_moves.clear();
+        self.rollout_actions.clear();
+        let mut legal_moves = core::mem::take(&mut self.legal_moves);
+
+        let outcome = loop {
+            if state.is_terminal() {
+                break self.terminal_outcome(state, state.get_winner());
+            }
+            if self
+                .max_rollout_depth
+                .is_some_and(|max_depth| depth >= max_depth)
+            {
+                break Ok(Outcome::Score(state.evaluate().clamp(0.0, 1.0)));
+            }
+            depth += 1;
+
+            if state.is_chance_node() {
+                match sample_outcome(&state.chance_outcomes(), &mut self.rng) {
+                    Some(action) => {
+                        state.make_move(action);
+                        self.rollout_actions.push(action);
+                    }
+                    None => {
+                        let draw_value = self.draw_value.unwrap_or(0.5);
+                        break Ok(Outcome::Rewards(vec![draw_value; state.num_players()]));
+                    }
+                }
+                continue;
+            }
+            legal_moves.clear();
+            state.legal_moves_into(&mut legal_moves);
+            // A non-terminal state without moves breaks the `GameState`
+            // contract, but is better scored as a draw than left to panic
+            // the rollout policy.
+            if legal_moves.is_empty() {
+                let draw_value = self.draw_value.unwrap_or(0.5);
+                break Ok(Outcome::Rewards(vec![draw_value; state.num_players()]));
+            }
+            let action = self
+                .rollout_policy
+                .choose(state, &legal_moves, &mut self.rng);
+            debug_assert!(
+                legal_moves.contains(&action),
+                "the rollout policy chose an action outside get_legal_moves"
+            );
+            if self.rave.is_some() {
+                self.rollout_moves.push((state.current_player(), action));
+            }
+            state.make_move(action);
+            self.rollout_actions.push(action);
+        };
+        self.legal_moves = legal_moves;
+        Ok(self.finish_rollout(outcome?, depth, plies_from_root))
+    }
+
+    /// Takes the moves of the last rollout back from `state`, which it was
+    /// played on. Returns `false` if the game cannot undo moves, in which
+    /// case `state` has to be replaced.
+    fn undo_rollout(&mut self, state: &mut dyn GameState<Action = A>) -> bool {
+        while let Some(&action) = self.rollout_actions.last() {
+            if !state.undo_move(action) {
+                return false;
+            }
+            self.rollout_actions.pop();
+        }
+        true
+    }
+
+    /// The rewards of the players of a game that ended in `state` with result
+    /// `winner`, as reported by the reward function if one is set and by
+    /// [`GameState::terminal_reward`] otherwise.
+    fn terminal_outcome(
+        &self,
+        state: &dyn GameState<Action = A>,
+        winner: Option<GameResult>,
+    ) -> Result<Outcome, MctsError> {
+        let result = match winner {
+            Some(GameResult::Win(player)) if player >= state.num_players() => {
+                return Err(MctsError::InvalidWinner(winner));
+            }
+            Some(result) => result,
+            None => return Err(MctsError::InvalidWinner(None)),
+        };
+        let rewards = (0..state.num_players())
+            .map(|player| match (&self.reward_fn, self.draw_value, result) {
+                (Some(reward_fn), _, _) => self.reward_scale.normalize(reward_fn(result, player)),
+                (None, Some(draw_value), GameResult::Draw) => draw_value,
+                (None, _, _) => state.terminal_reward(result, player),
+            })
+            .map(|reward| reward.clamp(0.0, 1.0))
+            .collect();
+        Ok(Outcome::Rewards(rewards))
+    }
+
+    /// Records a rollout of `depth` moves and applies the discount to its
+    /// `outcome`.
+    fn finish_rollout(
+        &mut self,
+        outcome: Outcome,
+        depth: usize,
+        plies_from_root: usize,
+    ) -> Outcome {
+        self.rollouts += 1;
+        self.rollout_plies += depth as u64;
+
+        // Count the plies from the root, so that a win found in the tree is
+        // discounted like one found in the rollout.
+        self.discounted(outcome, plies_from_root + depth)
+    }
+
+    /// Applies the discount of the engine to the outcome of a game that
+    /// ended `plies` moves below the root.
+    fn discounted(&self, outcome: Outcome, plies: usize) -> Outcome {
+        if self.discount == 1.0 {
+            return outcome;
+        }
+        outcome.discounted(self.discount.powi(plies as i32))
+    }
+
+    /// Has every worker thread of [`MCTS::get_best_move_parallel`] play one
+    /// rollout from `start`, and averages their rewards.
+    #[cfg(feature = "parallel")]
+    fn parallel_rollouts(
+        &mut self,
+        start: Box<dyn GameState<Action = A>>,
+        plies_from_root: usize,
+    ) -> Result<Outcome, MctsError> {
+        let workers = self.workers.as_ref().unwrap();
+        let threads = workers.jobs.len();
+        for (index, jobs) in workers.jobs.iter().enumerate() {
+            let seed = self.rng.gen();
+            jobs.send((index, start.box_clone(), seed))
+                .expect("rollout thread panicked");
+        }
+        // Results arrive in whatever order the threads finish, so put them
+        // back in order for the search to stay reproducible.
+        let mut playouts: Vec<Option<Playout<A>>> = (0..threads).map(|_| None).collect();
+        for _ in 0..threads {
+            let (index, playout) = workers.results.recv().expect("rollout thread panicked");
+            playouts[index] = Some(playout);
+        }
+
+        let mut totals = vec![0.0; start.num_players()];
+        for (index, playout) in playouts.into_iter().flatten().enumerate() {
+            let outcome = if playout.cut_off {
+                Outcome::Score(playout.state.evaluate().clamp(0.0, 1.0))
+            } else if playout.stuck {
+                let draw_value = self.draw_value.unwrap_or(0.5);
+                Outcome::Rewards(vec![draw_value; playout.state.num_players()])
+            } else {
+                self.terminal_outcome(playout.state.as_ref(), playout.state.get_winner())?
+            };
+            let outcome = self.finish_rollout(outcome, playout.depth, plies_from_root);
+            for (player, total) in totals.iter_mut().enumerate() {
+                *total += outcome.reward_for(player);
+            }
+            // The AMAF statistics are updated from a single rollout.
+            if index == 0 {
+                self.rollout_moves = playout.moves;
+            }
+        }
+        let threads = threads as f64;
+        Ok(Outcome::Rewards(
+            totals.into_iter().map(|total| total / threads).collect(),
+        ))
+    }
+
+    /// Adds `index` to the current path, charging it the virtual loss until
+    /// the iteration is backpropagated.
+    fn enter(&mut self, index: NodeIndex) {
+        self.path.push(index);
+        if self.virtual_loss != 0.0 {
+            self.visits[index] += self.virtual_loss;
+        }
+    }
+
+    fn revert_virtual_loss(&mut self) {
+        if self.virtual_loss == 0.0 {
+            return;
+        }
+        for &index in &self.path {
+            self.visits[index] -= self.virtual_loss;
+        }
+    }
+
+    /// Credits `outcome` to every node on the current path. Each node gets
+    /// the reward of the player who moved into it, so a parent and its child
+    /// accumulate complementary values in a two-player game, and a draw is
+    /// worth the same to everyone.
+    fn backpropagate(&mut self, outcome: Outcome) {
+        for &index in self.path.iter().rev() {
+            let node = &mut self.nodes[index];
+            let reward = outcome.reward_for(node.player_just_moved);
+            node.sum_sq += reward * reward;
+            if self.selection_policy == SelectionPolicy::Exp3 {
+                node.exp3_gain += reward / node.exp3_probability;
+            }
+            self.wins[index] += reward;
+            self.visits[index] += 1.0 - self.virtual_loss;
+            trace_event!(
+                node = index,
+                reward,
+                visits = self.visits[index],
+                "backpropagate"
+            );
+            if self.backup == BackupMode::Minimax {
+                self.nodes[index].minimax = self.minimax_value(index);
+            }
+        }
+        if self.rave.is_some() {
+            self.update_amaf(&outcome);
+        }
+    }
+
+    /// The value of `index` for its `player_just_moved` under
+    /// [`BackupMode::Minimax`]: that of its best visited child for the player
+    /// choosing there, or its mean reward at a leaf or a chance node.
+    fn minimax_value(&self, index: NodeIndex) -> f64 {
+        let node = &self.nodes[index];
+        let mean = self.wins[index] / self.visits[index];
+        if node.chance.is_some() {
+            return mean;
+        }
+        let best = node
+            .children
+            .iter()
+            .copied()
+            .filter(|&child| self.visits[child] > 0.0)
+            .max_by(|&a, &b| compare_values(self.nodes[a].minimax, self.nodes[b].minimax));
+        match best {
+            None => mean,
+            Some(child) if self.nodes[child].player_just_moved == node.player_just_moved => {
+                self.nodes[child].minimax
+            }
+            Some(child) => 1.0 - self.nodes[child].minimax,
+        }
+    }
+
+    /// Credits every node on the current path with the moves its player to
+    /// move made later in the iteration, in the tree or in the rollout. Only
+    /// the first occurrence of each move counts.
+    fn update_amaf(&mut self, outcome: &Outcome) {
+        // Kept latest-first, so that walking it from the back visits moves in
+        // the order they were played while tree moves are pushed on the end.
+        let mut played = core::mem::take(&mut self.rollout_moves);
+        played.reverse();
+        for &index in self.path.iter().rev() {
+            let node = &mut self.nodes[index];
+            let mover = node.state.current_player();
+            let reward = outcome.reward_for(mover);
+            let mut seen = Vec::new();
+            for &(player, action) in played.iter().rev() {
+                if player != mover || seen.contains(&action) {
+                    continue;
+                }
+                seen.push(action);
+                match node.amaf.iter_mut().find(|entry| entry.0 == action) {
+                    Some(entry) => {
+                        entry.1 += reward;
+                        entry.2 += 1.0;
+                    }
+                    None => node.amaf.push((action, reward, 1.0)),
+                }
+            }
+            if let Some(action) = node.last_action {
+                played.push((node.player_just_moved, action));
+            }
+        }
+        self.rollout_moves = played;
+    }
+
+    /// Runs a single select/expand/simulate/backpropagate cycle. Returns
+    /// `false` once the solver has proven the root, and there is nothing
+    /// left to search.
+    fn run_iteration(&mut self) -> Result<bool, MctsError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("iteration").entered();
+        if self.root_solved() {
+            return Ok(false);
+        }
+        let selected_node = self.select(self.root);
+        trace_event!(node = selected_node, depth = self.path.len() - 1, "select");
+        // Leaves that cannot grow are scored where they are: terminal leaves
+        // back up their result again, and the leaves of a full tree are
+        // rolled out from.
+        let expanded_node = self.expand(selected_node).unwrap_or(selected_node);
+        trace_event!(parent = selected_node, node = expanded_node, "expand");
+        let outcome = self
+            .simulate(expanded_node)
+            .inspect_err(|_| self.revert_virtual_loss())?;
+        trace_event!(
+            node = expanded_node,
+            reward = outcome.reward_for(self.nodes[expanded_node].player_just_moved),
+            "simulate"
+        );
+        self.backpropagate(outcome);
+        self.update_proofs();
+        Ok(true)
+    }
+
+    fn root_solved(&self) -> bool {
+        self.solver && self.nodes[self.root].proven.is_some()
+    }
+
+    /// Under the solver, proves what can newly be proven along the current
+    /// path, from the leaf up.
+    fn update_proofs(&mut self) {
+        if !self.solver {
+            return;
+        }
+        for position in (0..self.path.len()).rev() {
+            let index = self.path[position];
+            if self.nodes[index].proven.is_some() {
+                continue;
+            }
+            match self.prove(index) {
+                Some(result) => self.nodes[index].proven = Some(result),
+                None => break,
+            }
+        }
+    }
+
+    /// The result of `index` under best play, if its children prove it.
+    fn prove(&self, index: NodeIndex) -> Option<GameResult> {
+        let node = &self.nodes[index];
+        let mover = node.state.current_player();
+        let results = node.children.iter().map(|&child| self.nodes[child].proven);
+        // Nobody chooses the outcome of a chance node, so a single winning
+        // outcome proves nothing.
+        if node.chance.is_none()
+            && results
+                .clone()
+                .any(|result| result == Some(GameResult::Win(mover)))
+        {
+            return Some(GameResult::Win(mover));
+        }
+        if !node.untried_actions.is_empty() || node.children.is_empty() {
+            return None;
+        }
+        let results: Vec<GameResult> = results.collect::<Option<_>>()?;
+        if node.chance.is_none() && results.contains(&GameResult::Draw) {
+            return Some(GameResult::Draw);
+        }
+        let first = results[0];
+        results
+            .iter()
+            .all(|&result| result == first)
+            .then_some(first)
+    }
+
+    /// Runs a batch of up to `size` iterations whose leaves are scored by a
+    /// single call to the leaf evaluator. Returns how many iterations were
+    /// completed, and whether the solver has proven the root.
+    fn run_batch(&mut self, size: u32) -> Result<(u32, bool), MctsError> {
+        let mut completed = 0;
+        let mut solved = false;
+        let mut pending: Vec<(Vec<NodeIndex>, NodeIndex)> = Vec::new();
+        for _ in 0..size {
+            if self.root_solved() {
+                solved = true;
+                break;
+            }
+            let selected_node = self.select(self.root);
+            trace_event!(node = selected_node, depth = self.path.len() - 1, "select");
+            let expanded_node = self.expand(selected_node).unwrap_or(selected_node);
+            trace_event!(parent = selected_node, node = expanded_node, "expand");
+            if self.nodes[expanded_node].terminal {
+                let outcome = match self.simulate(expanded_node) {
+                    Ok(outcome) => outcome,
+                    Err(error) => {
+                        // The leaves already gathered will not be
+                        // backpropagated either.
+                        self.revert_virtual_loss();
+                        for (path, _) in pending {
+                            self.path = path;
+                            self.revert_virtual_loss();
+                        }
+                        return Err(error);
+                    }
+                };
+                self.backpropagate(outcome);
+                self.update_proofs();
+                completed += 1;
+            } else {
+                pending.push((core::mem::take(&mut self.path), expanded_node));
+            }
+        }
+        if pending.is_empty() {
+            return Ok((completed, solved));
+        }
+
+        let states: Vec<&dyn GameState<Action = A>> = pending
+            .iter()
+            .map(|&(_, leaf)| self.nodes[leaf].state.as_ref())
+            .collect();
+        let evaluator = self.leaf_evaluator.as_mut().unwrap();
+        let values = evaluator.evaluate_batch(&states);
+        assert_eq!(
+            values.len(),
+            pending.len(),
+            "the leaf evaluator must return one value per state"
+        );
+        for ((path, _), value) in pending.into_iter().zip(values) {
+            self.path = path;
+            self.rollout_moves.clear();
+            let plies_from_root = self.path.len() - 1;
+            let outcome = Outcome::Score(self.reward_scale.normalize(value).clamp(0.0, 1.0));
+            let outcome = self.finish_rollout(outcome, 0, plies_from_root);
+            self.backpropagate(outcome);
+            completed += 1;
+        }
+        Ok((completed, solved))
+    }
+
+    /// Runs a single iteration of information set MCTS. The root state is
+    /// determinized afresh and the tree is walked with the moves legal in
+    /// that sample, so nodes stand for move sequences rather than states.
+    fn run_determinized_iteration(&mut self) -> Result<(), MctsError> {
+        self.path.clear();
+        let mut state = self.nodes[self.root].state.determinize(&mut self.rng);
+        let mut current = self.root;
+        self.enter(current);
+
+        while !state.is_terminal() {
+            let legal_moves = state.get_legal_moves();
+            let children = &self.nodes[current].children;
+            let untried: Vec<A> = legal_moves
+                .iter()
+                .copied()
+                .filter(|&action| {
+                    !children
+                        .iter()
+                        .any(|&child| self.nodes[child].last_action == Some(action))
+                })
+                .collect();
+
+            if !untried.is_empty() && !self.is_full() {
+                let action = untried[self.rng.gen_range(0..untried.len())];
+                let mover = state.current_player();
+                state.make_move(action);
+                let new_index = self.push_node(Node::new(
+                    state.box_clone(),
+                    Some(current),
+                    Some(action),
+                    mover,
+                ));
+                self.nodes[current].children.push(new_index);
+                self.enter(new_index);
+                break;
+            }
+
+            // Only the children whose move exists in this determinization
+            // compete.
+            let compatible = children.iter().copied().filter(|&child| {
+                self.nodes[child]
+                    .last_action
+                    .is_some_and(|action| legal_moves.contains(&action))
+            });
+            let child = self.best_uct_child(current, compatible);
+            let Some(child) = child else {
+                break;
+            };
+            state.make_move(self.nodes[child].last_action.unwrap());
+            self.enter(child);
+            current = child;
+        }
+
+        let outcome = self
+            .rollout(state.as_mut(), self.path.len() - 1)
+            .inspect_err(|_| self.revert_virtual_loss())?;
+        self.backpropagate(outcome);
+        Ok(())
+    }
+
+    fn check_root(&self) -> Result<(), MctsError> {
+        let root = &self.nodes[self.root];
+        if root.terminal {
+            return Err(MctsError::TerminalRoot);
+        }
+        if root.untried_actions.is_empty() && root.children.is_empty() {
+            return Err(MctsError::NoLegalMoves);
+        }
+        Ok(())
+    }
+
+    /// Returns the only legal move of the root, if it has exactly one, so
+    /// that searches can skip straight to it.
+    fn forced_move(&self) -> Option<A> {
+        let root = &self.nodes[self.root];
+        match (root.untried_actions.as_slice(), root.children.as_slice()) {
+            (&[action], []) => Some(action),
+            ([], &[child]) => self.nodes[child].last_action,
+            _ => None,
+        }
+    }
+
+    /// Picks the most visited child of `node_index`, see
+    /// [`MCTS::compare_visits`].
+    fn most_visited_child(&self, node_index: NodeIndex) -> Option<NodeIndex> {
+        self.nodes[node_index]
+            .children
+            .iter()
+            .copied()
+            .max_by(|&a, &b| self.compare_visits(a, b))
+    }
+
+    /// Orders nodes by their visits. Ties go to the node with the higher
+    /// average reward, then to the node created first.
+    fn compare_visits(&self, a: NodeIndex, b: NodeIndex) -> core::cmp::Ordering {
+        compare_values(self.visits[a], self.visits[b])
+            .then(compare_values(
+                self.average_reward(a),
+                self.average_reward(b),
+            ))
+            .then(b.cmp(&a))
+    }
+
+    /// The average reward of `index` for the player who moved into it,
+    /// `0.0` if it was never visited.
+    fn average_reward(&self, index: NodeIndex) -> f64 {
+        if self.visits[index] > 0.0 {
+            self.wins[index] / self.visits[index]
+        } else {
+            0.0
+        }
+    }
+
+    fn highest_value_child(&self) -> Option<NodeIndex> {
+        self.nodes[self.root]
+            .children
+            .iter()
+            .copied()
+            .filter(|&child| self.visits[child] > 0.0)
+            .max_by(|&a, &b| {
+                let value_a = self.wins[a] / self.visits[a];
+                let value_b = self.wins[b] / self.visits[b];
+                compare_values(value_a, value_b).then(b.cmp(&a))
+            })
+    }
+
+    /// Under the solver, the root child that achieves the root's proven win
+    /// or draw.
+    fn proven_child(&self) -> Option<NodeIndex> {
+        let root = &self.nodes[self.root];
+        let result = root.proven.filter(|_| self.solver)?;
+        if result != GameResult::Draw && result != GameResult::Win(root.state.current_player()) {
+            return None;
+        }
+        root.children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].proven == Some(result))
+    }
+
+    fn best_action(&self) -> Result<A, MctsError> {
+        self.best_action_with(FinalMoveSelection::MostVisits)
+    }
+
+    fn best_action_with(&self, policy: FinalMoveSelection) -> Result<A, MctsError> {
+        if let Some(child) = self.proven_child() {
+            return Ok(self.nodes[child].last_action.unwrap());
+        }
+        let best_child = match policy {
+            FinalMoveSelection::MostVisits | FinalMoveSelection::Robust => {
+                self.most_visited_child(self.root)
+            }
+            FinalMoveSelection::HighestValue => self.highest_value_child(),
+        }
+        .ok_or(MctsError::NoLegalMoves)?;
+
+        Ok(self.nodes[best_child].last_action.unwrap())
+    }
+
+    /// Runs `iterations` more iterations and returns the most visited root
+    /// move, like [`MCTS::run_iterations`] followed by [`MCTS::best_move`],
+    /// except that a forced move is returned without searching.
+    pub fn get_best_move(&mut self, iterations: u32) -> Result<A, MctsError> {
+        self.get_best_move_with_callback(iterations, |_, _| ControlFlow::Continue(()))
+    }
+
+    /// Grows the tree by up to `iterations` iterations without choosing a
+    /// move. The tree persists between calls, so a search can be spread
+    /// over several calls interleaved with other work: with a fixed seed,
+    /// two calls of 500 iterations build the same tree as one of 1000,
+    /// unless [`MCTS::set_early_stop`] cuts one of them short.
+    pub fn run_iterations(&mut self, iterations: u32) -> Result<(), MctsError> {
+        self.check_root()?;
+        self.search(iterations, |_, _| ControlFlow::Continue(()))?;
+        Ok(())
+    }
+
+    /// The move [`MCTS::get_best_move`] would return from the tree as it
+    /// stands, without running any iterations. `None` if the root is
+    /// terminal or nothing has been searched yet.
+    pub fn best_move(&self) -> Option<A> {
+        self.forced_move().or_else(|| self.best_action().ok())
+    }
+
+    /// Runs up to `iterations` iterations, calling `callback` after each one
+    /// with the index of the iteration and the engine, and returns the most
+    /// visited root move. The search stops early if `callback` returns
+    /// [`ControlFlow::Break`], which makes it possible to report progress or
+    /// stop once [`MCTS::move_statistics`] has settled. With a batching leaf
+    /// evaluator the callback runs once per batch, with the index of the
+    /// batch's last iteration.
+    pub fn get_best_move_with_callback(
+        &mut self,
+        iterations: u32,
+        callback: impl FnMut(u32, &Self) -> ControlFlow<()>,
+    ) -> Result<A, MctsError> {
+        self.check_root()?;
+        if let Some(action) = self.forced_move() {
+            return Ok(action);
+        }
+        self.search(iterations, callback)?;
+        self.best_action()
+    }
+
+    /// Like [`MCTS::get_best_move`], but also returns statistics about the
+    /// search.
+    pub fn get_best_move_report(
+        &mut self,
+        iterations: u32,
+    ) -> Result<(A, SearchReport), MctsError> {
+        let nodes_before = self.nodes.len();
+        let rollouts_before = self.rollouts;
+        let plies_before = self.rollout_plies;
+        let mut completed = 0;
+        let action = self.get_best_move_with_callback(iterations, |iteration, _| {
+            completed = iteration + 1;
+            ControlFlow::Continue(())
+        })?;
+
+        let rollouts = self.rollouts - rollouts_before;
+        let average_rollout_length = if rollouts > 0 {
+            (self.rollout_plies - plies_before) as f64 / rollouts as f64
+        } else {
+            0.0
+        };
+        let report = SearchReport {
+            iterations: completed,
+            nodes_created: self.nodes.len().saturating_sub(nodes_before),
+            average_rollout_length,
+            max_depth: self.tree_depth(),
+        };
+        Ok((action, report))
+    }
+
+    /// Runs up to `max_iterations` iterations, but stops as soon as the most
+    /// visited root move is clearly better than the runner-up: when the Wald
+    /// confidence intervals `p ± z * sqrt(p * (1 - p) / visits)` on their
+    /// average rewards no longer overlap. A `z` of `1.96` corresponds to 95%
+    /// intervals. The intervals are only compared every 64 iterations.
+    pub fn get_best_move_confident(&mut self, max_iterations: u32, z: f64) -> Result<A, MctsError> {
+        let mut next_check = TIME_CHECK_INTERVAL;
+        self.get_best_move_with_callback(max_iterations, |iteration, engine| {
+            if iteration + 1 < next_check {
+                return ControlFlow::Continue(());
+            }
+            next_check += TIME_CHECK_INTERVAL;
+            if engine.best_move_separated(z) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+    }
+
+    /// Whether the confidence interval on the average reward of the most
+    /// visited root child lies above that of the runner-up by visits. A root
+    /// with a single move and nothing left to expand is always separated.
+    fn best_move_separated(&self, z: f64) -> bool {
+        let root = &self.nodes[self.root];
+        let mut children: Vec<NodeIndex> = root.children.clone();
+        children.sort_by(|&a, &b| compare_values(self.visits[b], self.visits[a]));
+        let interval = |child: NodeIndex| {
+            let visits = self.visits[child];
+            let mean = self.wins[child] / visits;
+            let half_width = z * (mean * (1.0 - mean) / visits).sqrt();
+            (mean - half_width, mean + half_width)
+        };
+        match children[..] {
+            [best, second, ..] if self.visits[second] > 0.0 => {
+                interval(best).0 > interval(second).1
+            }
+            [_] => root.untried_actions.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Runs up to `iterations` iterations. Returns `true` if the solver
+    /// proved the root, and `false` if it ran to the end or was
+    /// stopped early by `callback` or the early-stop check.
+    fn search(
+        &mut self,
+        iterations: u32,
+        mut callback: impl FnMut(u32, &Self) -> ControlFlow<()>,
+    ) -> Result<bool, MctsError> {
+        let mut done = 0;
+        while done < iterations {
+            let batch_size = match self.leaf_evaluator {
+                Some(_) => self.batch_size as u32,
+                None => 1,
+            };
+            let (completed, solved) = if batch_size > 1 {
+                self.run_batch(batch_size.min(iterations - done))?
+            } else {
+                let searched = self.run_iteration()?;
+                (searched as u32, !searched)
+            };
+            done += completed;
+            if solved {
+                return Ok(true);
+            }
+            if callback(done - 1, self).is_break() {
+                break;
+            }
+            // Batches can step over a multiple of the check interval.
+            if self.early_stop
+                && done % TIME_CHECK_INTERVAL < completed
+                && self.decision_locked(iterations - done)
+            {
+                break;
+            }
+        }
+        Ok(false)
+    }
+
+    /// Whether the most visited root child leads every other child by more
+    /// than `remaining` visits.
+    fn decision_locked(&self, remaining: u32) -> bool {
+        let mut best = 0.0;
+        let mut second = 0.0;
+        for &child in &self.nodes[self.root].children {
+            let visits = self.visits[child];
+            if visits > best {
+                second = best;
+                best = visits;
+            } else if visits > second {
+                second = visits;
+            }
+        }
+        best - second > remaining as f64
+    }
+
+    /// Runs `iterations` iterations and picks the root move using `policy`.
+    ///
+    /// With [`FinalMoveSelection::Robust`] the search keeps going for up to
+    /// `iterations` extra iterations while the most visited and the highest
+    /// valued children disagree.
+    pub fn get_best_move_with(
+        &mut self,
+        iterations: u32,
+        policy: FinalMoveSelection,
+    ) -> Result<A, MctsError> {
+        self.check_root()?;
+        if let Some(action) = self.forced_move() {
+            return Ok(action);
+        }
+        let solved = self.search(iterations, |_, _| ControlFlow::Continue(()))?;
+
+        if policy == FinalMoveSelection::Robust && !solved {
+            for _ in 0..iterations {
+                if self.most_visited_child(self.root) == self.highest_value_child()
+                    || !self.run_iteration()?
+                {
+                    break;
+                }
+            }
+        }
+
+        self.best_action_with(policy)
+    }
+
+    /// Samples a root move with probability proportional to
+    /// `visits^(1 / temperature)`, using the engine RNG. A temperature of
+    /// `0.0` always returns the most visited move.
+    pub fn sample_move(&mut self, temperature: f64) -> Result<A, MctsError> {
+        if temperature == 0.0 {
+            return self.best_action();
+        }
+
+        let children = &self.nodes[self.root].children;
+        let max_visits = children
+            .iter()
+            .map(|&child| self.visits[child])
+            .fold(0.0, f64::max);
+        if max_visits == 0.0 {
+            return self.best_action();
+        }
+
+        // Scaling by the largest count keeps the powers from overflowing.
+        let weights: Vec<f64> = children
+            .iter()
+            .map(|&child| (self.visits[child] / max_visits).powf(1.0 / temperature))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        let mut threshold = self.rng.gen::<f64>() * total;
+        for (&child, weight) in children.iter().zip(&weights) {
+            if threshold < *weight {
+                return Ok(self.nodes[child].last_action.unwrap());
+            }
+            threshold -= weight;
+        }
+        self.best_action()
+    }
+
+    /// Returns `(action, visits, wins)` for every child of the root, most
+    /// visited first.
+    pub fn move_statistics(&self) -> Vec<(A, f64, f64)> {
+        let mut statistics: Vec<(A, f64, f64)> = self.nodes[self.root]
+            .children
+            .iter()
+            .map(|&child| {
+                let action = self.nodes[child].last_action.unwrap();
+                (action, self.visits[child], self.wins[child])
+            })
+            .collect();
+        statistics.sort_by(|a, b| compare_values(b.1, a.1));
+        statistics
+    }
+
+    /// Returns up to `k` root moves with their average rewards for the
+    /// player to move, most visited first and ranked like
+    /// [`MCTS::get_best_move`] ranks them, so the first is the move it
+    /// returns unless the solver has proven another.
+    pub fn best_moves(&self, k: usize) -> Vec<(A, f64)> {
+        let mut children = self.nodes[self.root].children.clone();
+        children.sort_by(|&a, &b| self.compare_visits(b, a));
+        children
+            .into_iter()
+            .take(k)
+            .map(|child| {
+                (
+                    self.nodes[child].last_action.unwrap(),
+                    self.reward_scale.rescale(self.average_reward(child)),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns `(action, visits)` for every child of the root, in the order
+    /// they were expanded. A lighter [`MCTS::move_statistics`] for drawing
+    /// the search, for example as a heatmap over a board.
+    pub fn root_action_visits(&self) -> Vec<(A, f64)> {
+        self.nodes[self.root]
+            .children
+            .iter()
+            .map(|&child| (self.nodes[child].last_action.unwrap(), self.visits[child]))
+            .collect()
+    }
+
+    /// Returns every legal root move, in the order of
+    /// [`GameState::get_legal_moves`], with its share of the visits to the
+    /// root's children: the policy target of AlphaZero-style training. Moves
+    /// that were never expanded get `0.0`. The shares sum to `1.0` once any
+    /// move has been visited, and are all `0.0` before.
+    pub fn policy_target(&self) -> Vec<(A, f64)> {
+        let root = &self.nodes[self.root];
+        let total: f64 = root.children.iter().map(|&child| self.visits[child]).sum();
+        root.state
+            .get_legal_moves()
+            .into_iter()
+            .map(|action| {
+                let visits = root
+                    .children
+                    .iter()
+                    .find(|&&child| self.nodes[child].last_action == Some(action))
+                    .map_or(0.0, |&child| self.visits[child]);
+                let share = if total > 0.0 { visits / total } else { 0.0 };
+                (action, share)
+            })
+            .collect()
+    }
+
+    /// Returns the average reward of the move [`MCTS::get_best_move`] picks,
+    /// the proven one under the solver and otherwise the most visited, as
+    /// seen by the player to move. This is an estimate of that player's
+    /// chance of winning. `None` until the search has visited a move.
+    pub fn best_child_value(&self) -> Option<f64> {
+        let best = self
+            .proven_child()
+            .or_else(|| self.most_visited_child(self.root))?;
+        (self.visits[best] > 0.0).then(|| {
+            self.reward_scale
+                .rescale(self.wins[best] / self.visits[best])
+        })
+    }
+
+    /// The position after the move [`MCTS::get_best_move`] would return, as
+    /// stored in the tree, so that it can be shown without applying the move
+    /// again. `None` while the root has no children.
+    pub fn best_child_state(&self) -> Option<&dyn GameState<Action = A>> {
+        let best = self
+            .proven_child()
+            .or_else(|| self.most_visited_child(self.root))?;
+        Some(self.nodes[best].state.as_ref())
+    }
+
+    /// Runs `iterations` iterations and returns the estimated reward of the
+    /// root position for the player to move, the [`MCTS::best_child_value`]
+    /// of the search. The best move is used rather than an average over all
+    /// moves, since exploring weak moves would drag an average towards a
+    /// loss. Unlike [`MCTS::get_best_move`] this searches even when only one
+    /// move is legal. A terminal root is scored directly, with no
+    /// search.
+    pub fn evaluate_position(&mut self, iterations: u32) -> Result<f64, MctsError> {
+        let root = &self.nodes[self.root];
+        if root.terminal {
+            let player = root.state.current_player();
+            let outcome = self.terminal_outcome(root.state.as_ref(), root.winner)?;
+            return Ok(self.reward_scale.rescale(outcome.reward_for(player)));
+        }
+        self.check_root()?;
+        self.search(iterations, |_, _| ControlFlow::Continue(()))?;
+
+        self.best_child_value().ok_or(MctsError::NoLegalMoves)
+    }
+
+    /// The result of the game from the root under best play, once the
+    /// solver enabled by [`MCTS::set_solver`] has proven it, or the result
+    /// of a terminal root.
+    pub fn proven_result(&self) -> Option<GameResult> {
+        self.nodes[self.root].proven
+    }
+
+    /// Returns the line of play the engine currently expects, found by
+    /// following the most visited child from the root down to a leaf.
+    pub fn principal_variation(&self) -> Vec<A> {
+        let mut variation = Vec::new();
+        let mut current = self.root;
+        while let Some(child) = self.most_visited_child(current) {
+            variation.push(self.nodes[child].last_action.unwrap());
+            current = child;
+        }
+        variation
+    }
+
+    /// Mixes Dirichlet(`alpha`) noise into the selection of the root's
+    /// current children, as in AlphaZero self-play: each child's prior (under
+    /// PUCT) or score (under UCB1) becomes `(1 - epsilon) * base + epsilon *
+    /// noise`. Deeper nodes are unaffected, and nothing happens if the root
+    /// has no children yet.
+    pub fn add_root_noise(&mut self, alpha: f64, epsilon: f64) {
+        let children = self.nodes[self.root].children.clone();
+        if children.is_empty() {
+            return;
+        }
+
+        let noise = sample_dirichlet(&mut self.rng, alpha, children.len());
+        for (child, noise) in children.into_iter().zip(noise) {
+            self.nodes[child].noise = Some(noise);
+        }
+        self.noise_epsilon = epsilon;
+    }
+
+    /// Writes the tree to `path`, encoding every state with
+    /// [`GameState::serialize_state`].
+    ///
+    /// Only the tree is saved: the configuration (exploration constant,
+    /// policies, limits) and the RNG are not, and [`MCTS::load`] starts from
+    /// the defaults of [`MCTS::new`]. Actions are stored as positions in the
+    /// owning state's `get_legal_moves()`, which must therefore be
+    /// deterministic.
+    ///
+    /// The file starts with the bytes `MCTS` and a format version, which
+    /// [`MCTS::load`] checks, so that other files and other versions of the
+    /// layout are rejected instead of misread. The format is written by hand
+    /// rather than derived with serde: the states are `Box<dyn GameState>`,
+    /// which only the caller knows how to rebuild, and a derived
+    /// `Deserialize` has no way to be handed the caller's decoder.
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = Vec::new();
+        out.extend_from_slice(SAVE_MAGIC);
+        write_u64(&mut out, SAVE_VERSION);
+        write_u64(&mut out, self.nodes.len() as u64);
+        write_u64(&mut out, self.root as u64);
+
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            let state = node.state.serialize_state().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "GameState::serialize_state is not implemented",
+                )
+            })?;
+            let legal_moves = node.state.get_legal_moves();
+            let move_index = |action: A| {
+                legal_moves
+                    .iter()
+                    .position(|&legal| legal == action)
+                    .map(|index| index as u64)
+                    .ok_or_else(|| invalid_data("action is not a legal move of its state"))
+            };
+
+            write_u64(&mut out, state.len() as u64);
+            out.extend_from_slice(&state);
+            write_optional_u64(&mut out, node.parent.map(|parent| parent as u64));
+            let last_action = match (node.parent, node.last_action) {
+                (Some(parent), Some(action)) => {
+                    let parent_moves = self.nodes[parent].state.get_legal_moves();
+                    let index = parent_moves
+                        .iter()
+                        .position(|&legal| legal == action)
+                        .ok_or_else(|| invalid_data("action is not a legal move of its parent"))?;
+                    Some(index as u64)
+                }
+                _ => None,
+            };
+            write_optional_u64(&mut out, last_action);
+            write_u64(&mut out, node.player_just_moved as u64);
+            write_f64(&mut out, self.wins[node_index]);
+            write_f64(&mut out, node.sum_sq);
+            write_f64(&mut out, self.visits[node_index]);
+            write_f64(&mut out, node.prior);
+
+            write_u64(&mut out, node.children.len() as u64);
+            for &child in &node.children {
+                write_u64(&mut out, child as u64);
+            }
+            write_u64(&mut out, node.untried_actions.len() as u64);
+            for &action in &node.untried_actions {
+                write_u64(&mut out, move_index(action)?);
+            }
+            match &node.priors {
+                Some(priors) => {
+                    write_u64(&mut out, priors.len() as u64);
+                    for &(action, prior) in priors {
+                        write_u64(&mut out, move_index(action)?);
+                        write_f64(&mut out, prior);
+                    }
+                }
+                None => write_u64(&mut out, u64::MAX),
+            }
+        }
+
+        fs::File::create(path)?.write_all(&out)
+    }
+
+    /// Reads a tree written by [`MCTS::save`], rebuilding every state with
+    /// `deserialize_state`. The engine uses the defaults of [`MCTS::new`], so
+    /// RAVE statistics are not saved.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the file does not start
+    /// with the header of the current format version, or does not describe a
+    /// valid tree.
+    #[cfg(feature = "std")]
+    pub fn load(
+        path: impl AsRef<Path>,
+        deserialize_state: impl Fn(&[u8]) -> Option<Box<dyn GameState<Action = A>>>,
+    ) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        fs::File::open(path)?.read_to_end(&mut bytes)?;
+        let mut input = bytes.as_slice();
+
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != SAVE_MAGIC || read_u64(&mut input)? != SAVE_VERSION {
+            return Err(invalid_data("not a saved MCTS tree"));
+        }
+        let node_count = read_u64(&mut input)? as usize;
+        let root = read_u64(&mut input)? as usize;
+
+        // Actions are resolved once every state is known, since a node's
+        // `last_action` refers to its parent's moves.
+        let mut nodes: Vec<Node<A>> = Vec::with_capacity(node_count);
+        let mut wins = Vec::with_capacity(node_count);
+        let mut visits = Vec::with_capacity(node_count);
+        let mut last_action_indices = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            let len = read_u64(&mut input)? as usize;
+            if input.len() < len {
+                return Err(invalid_data("truncated state"));
+            }
+            let (state_bytes, rest) = input.split_at(len);
+            input = rest;
+            let state = deserialize_state(state_bytes)
+                .ok_or_else(|| invalid_data("state could not be deserialized"))?;
+            let legal_moves = state.get_legal_moves();
+            let action_at = |index: u64| {
+                legal_moves
+                    .get(index as usize)
+                    .copied()
+                    .ok_or_else(|| invalid_data("move index out of range"))
+            };
+
+            let parent = read_optional_u64(&mut input)?.map(|parent| parent as usize);
+            last_action_indices.push(read_optional_u64(&mut input)?);
+            let player_just_moved = read_u64(&mut input)? as usize;
+            wins.push(read_f64(&mut input)?);
+            let sum_sq = read_f64(&mut input)?;
+            visits.push(read_f64(&mut input)?);
+            let prior = read_f64(&mut input)?;
+
+            let children = (0..read_u64(&mut input)?)
+                .map(|_| read_u64(&mut input).map(|child| child as usize))
+                .collect::<io::Result<Vec<_>>>()?;
+            let untried_actions = (0..read_u64(&mut input)?)
+                .map(|_| action_at(read_u64(&mut input)?))
+                .collect::<io::Result<Vec<_>>>()?;
+            let priors = match read_u64(&mut input)? {
+                u64::MAX => None,
+                len => Some(
+                    (0..len)
+                        .map(|_| Ok((action_at(read_u64(&mut input)?)?, read_f64(&mut input)?)))
+                        .collect::<io::Result<Vec<_>>>()?,
+                ),
+            };
+
+            let mut node = Node::new(state, parent, None, player_just_moved);
+            node.children = children;
+            node.sum_sq = sum_sq;
+            node.untried_actions = untried_actions;
+            node.prior = prior;
+            node.priors = priors;
+            nodes.push(node);
+        }
+
+        for index in 0..node_count {
+            if let (Some(parent), Some(move_index)) =
+                (nodes[index].parent, last_action_indices[index])
+            {
+                let parent_moves = nodes
+                    .get(parent)
+                    .ok_or_else(|| invalid_data("parent index out of range"))?
+                    .state
+                    .get_legal_moves();
+                let action = parent_moves
+                    .get(move_index as usize)
+                    .copied()
+                    .ok_or_else(|| invalid_data("move index out of range"))?;
+                nodes[index].last_action = Some(action);
+            }
+        }
+        MCTS::from_nodes(nodes, wins, visits, root).map_err(invalid_data)
+    }
+
+    /// Builds an engine with the defaults of [`MCTS::new`] around a tree
+    /// that was put together by hand, so that selection and backpropagation
+    /// can be checked against a known shape and known statistics. `wins`
+    /// and `visits` are indexed like `nodes`.
+    ///
+    /// Fails with a description of the problem unless every index is in
+    /// range, every node comes after the parent it names, every parent lists
+    /// the children that name it as their parent, and no chain of children
+    /// leads back to where it started. A transposed child only names the
+    /// first of its parents.
+    #[cfg(any(feature = "std", test))]
+    fn from_nodes(
+        nodes: Vec<Node<A>>,
+        wins: Vec<f64>,
+        visits: Vec<f64>,
+        root: NodeIndex,
+    ) -> Result<Self, &'static str> {
+        if wins.len() != nodes.len() || visits.len() != nodes.len() {
+            return Err("statistics do not match the nodes");
+        }
+        if root >= nodes.len()
+            || nodes
+                .iter()
+                .flat_map(|node| &node.children)
+                .any(|&child| child >= nodes.len())
+        {
+            return Err("node index out of range");
+        }
+        for (index, node) in nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                if parent >= index {
+                    return Err("node comes before its parent");
+                }
+                if !nodes[parent].children.contains(&index) {
+                    return Err("node is missing from its parent's children");
+                }
+            }
+            if node
+                .children
+                .iter()
+                .any(|&child| nodes[child].parent.is_none())
+            {
+                return Err("child has no parent");
+            }
+        }
+        // Transposed children may come before a later parent, so the parent
+        // order alone does not rule out cycles.
+        if has_cycle(&nodes) {
+            return Err("children form a cycle");
+        }
+
+        let root_state = nodes[root].state.box_clone();
+        let mut mcts = MCTS::new(root_state);
+        mcts.transpositions.clear();
+        for (index, node) in nodes.iter().enumerate() {
+            if let Some(key) = node.state.state_key() {
+                mcts.transpositions.entry(key).or_insert(index);
+            }
+        }
+        mcts.nodes = nodes;
+        mcts.wins = wins;
+        mcts.visits = visits;
+        mcts.root = root;
+        Ok(mcts)
+    }
+
+    /// Renders the tree down to `max_depth` as a Graphviz DOT digraph. Each
+    /// node is labelled with its `wins/visits` and each edge with the move
+    /// that leads to the child.
+    pub fn to_dot(&self, max_depth: usize) -> String
+    where
+        A: fmt::Debug,
+    {
+        let mut dot = String::from("digraph MCTS {\n");
+        let mut declared = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::from([(self.root, 0)]);
+        declared[self.root] = true;
+
+        while let Some((index, depth)) = queue.pop_front() {
+            let node = &self.nodes[index];
+            dot.push_str(&format!(
+                "    n{} [label=\"{:.1}/{}\"];\n",
+                index, self.wins[index], self.visits[index]
+            ));
+            if depth == max_depth {
+                continue;
+            }
+            for &child in &node.children {
+                let label = format!("{:?}", self.nodes[child].last_action.unwrap());
+                dot.push_str(&format!(
+                    "    n{} -> n{} [label=\"{}\"];\n",
+                    index,
+                    child,
+                    label.replace('"', "\\\"")
+                ));
+                if !declared[child] {
+                    declared[child] = true;
+                    queue.push_back((child, depth + 1));
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Index of the root node.
+    pub fn root(&self) -> NodeIndex {
+        self.root
+    }
+
+    /// Index of the most visited child of the root, the node whose move
+    /// [`MCTS::get_best_move`] returns.
+    pub fn most_visited_child_index(&self) -> Option<NodeIndex> {
+        self.most_visited_child(self.root)
+    }
+
+    /// Children of node `index`.
+    ///
+    /// Panics if `index` is not a node of the tree, as do the other node
+    /// accessors.
+    pub fn children_of(&self, index: NodeIndex) -> &[NodeIndex] {
+        &self.nodes[index].children
+    }
+
+    /// The `(wins, visits)` statistics of node `index`. Wins are counted
+    /// for the player who made the move leading to it.
+    pub fn wins_visits(&self, index: NodeIndex) -> (f64, f64) {
+        (self.wins[index], self.visits[index])
+    }
+
+    /// The move leading to node `index`, `None` for the root of a new engine.
+    pub fn last_action_of(&self, index: NodeIndex) -> Option<A> {
+        self.nodes[index].last_action
+    }
+
+    /// Whether every move at node `index` already has a child. Unlike the
+    /// other node accessors this does not panic, and is `false` for an
+    /// index outside the tree.
+    pub fn is_fully_expanded(&self, index: NodeIndex) -> bool {
+        self.nodes
+            .get(index)
+            .is_some_and(|node| node.untried_actions.is_empty())
+    }
+
+    /// Whether every root move already has a child, so that further
+    /// iterations only deepen the tree below them.
+    pub fn is_root_fully_expanded(&self) -> bool {
+        self.is_fully_expanded(self.root)
+    }
+
+    /// Number of simulations since the engine was created or last
+    /// [`reset`](MCTS::reset), counting every rollout, leaf evaluation and
+    /// terminal result that scored a leaf.
+    pub fn num_simulations(&self) -> u64 {
+        self.rollouts
+    }
+
+    /// Number of nodes [`MCTS::advance_root`] has carried over into new
+    /// searches since the engine was created or last reset.
+    pub fn reuse_count(&self) -> u64 {
+        self.nodes_reused
+    }
+
+    /// Number of nodes the tree can hold before its storage is reallocated.
+    pub fn node_capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Number of nodes in the tree, including the root.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Length of the longest path from the root to a leaf. A fresh engine
+    /// has a depth of 0.
+    pub fn tree_depth(&self) -> usize {
+        self.node_depths().into_iter().max().unwrap_or(0)
+    }
+
+    /// Mean number of children of the nodes that have any, `0.0` while the
+    /// root has none. Unexpanded moves are not counted, so this measures the
+    /// tree rather than the game.
+    pub fn average_branching_factor(&self) -> f64 {
+        let (parents, children) = self
+            .nodes
+            .iter()
+            .filter(|node| !node.children.is_empty())
+            .fold((0, 0), |(parents, children), node| {
+                (parents + 1, children + node.children.len())
+            });
+        if parents == 0 {
+            return 0.0;
+        }
+        children as f64 / parents as f64
+    }
+
+    /// Mean depth of every node in the tree, including the root at depth 0.
+    pub fn average_depth(&self) -> f64 {
+        let depths = self.node_depths();
+        if depths.is_empty() {
+            return 0.0;
+        }
+        depths.iter().sum::<usize>() as f64 / depths.len() as f64
+    }
+
+    /// Depth of every node along its `parent` links. Parents are always
+    /// stored before their children, so a single forward pass suffices.
+    fn node_depths(&self) -> Vec<usize> {
+        let mut depths = vec![0; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                depths[index] = depths[parent] + 1;
+            }
+        }
+        depths
+    }
+
+    /// Returns an independent copy of the engine and its tree, for trying
+    /// out moves or longer searches without touching this one. Its settings
+    /// and RNG state are copied too, except for the boxed rollout policy,
+    /// leaf evaluator, reward function and selection function, which cannot
+    /// be: the copy plays random rollouts, scores results by
+    /// [`GameState::terminal_reward`] and selects by its
+    /// [`SelectionPolicy`] until they are set again.
+    pub fn clone_tree(&self) -> MCTS<A> {
+        MCTS {
+            nodes: self.nodes.clone(),
+            wins: self.wins.clone(),
+            visits: self.visits.clone(),
+            root: self.root,
+            exploration_constant: self.exploration_constant,
+            rng: self.rng.clone(),
+            selection_policy: self.selection_policy,
+            c_puct: self.c_puct,
+            exp3_gamma: self.exp3_gamma,
+            rollout_policy: Box::new(RandomRollout),
+            max_rollout_depth: self.max_rollout_depth,
+            transpositions: self.transpositions.clone(),
+            path: Vec::new(),
+            virtual_loss: self.virtual_loss,
+            noise_epsilon: self.noise_epsilon,
+            widening: self.widening,
+            max_nodes: self.max_nodes,
+            discount: self.discount,
+            progressive_bias: self.progressive_bias,
+            expand_all: self.expand_all,
+            early_stop: self.early_stop,
+            solver: self.solver,
+            rave: self.rave,
+            reward_fn: None,
+            selection_fn: None,
+            draw_value: self.draw_value,
+            reward_scale: self.reward_scale,
+            rollouts_per_leaf: self.rollouts_per_leaf,
+            backup: self.backup,
+            parent_visits: self.parent_visits,
+            leaf_evaluator: None,
+            batch_size: 1,
+            rollout_moves: Vec::new(),
+            legal_moves: Vec::new(),
+            rollout_actions: Vec::new(),
+            pending_chance: None,
+            rollouts: self.rollouts,
+            rollout_plies: self.rollout_plies,
+            nodes_reused: self.nodes_reused,
+            #[cfg(feature = "parallel")]
+            workers: None,
+        }
+    }
+
+    /// Moves the root to the child reached by `action`, keeping the
+    /// statistics gathered below it and dropping the rest of the tree.
+    ///
+    /// If `action` was never expanded the tree is rebuilt from the resulting
+    /// state.
+    pub fn advance_root(&mut self, action: A) {
+        let new_root = self.nodes[self.root]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].last_action == Some(action));
+
+        match new_root {
+            Some(new_root) => {
+                self.retain_subtree(new_root, |_| true);
+                self.nodes_reused += self.nodes.len() as u64;
+            }
+            None => {
+                let root_state = &self.nodes[self.root].state;
+                let mover = root_state.current_player();
+                let state = root_state.apply(action);
+                self.install_root(state, Some(action), mover);
+            }
+        }
+    }
+
+    /// Plays `actions` from the root in turn, as [`MCTS::advance_root`]
+    /// would one at a time, so that the search starts from the position
+    /// they lead to, such as an opening from a book or a reported game.
+    /// Chance outcomes are played like any other move. If one of the moves
+    /// cannot be played, its position in `actions` is returned in
+    /// [`MctsError::IllegalMove`] and the tree is left as it was.
+    pub fn play_sequence(&mut self, actions: &[A]) -> Result<(), MctsError> {
+        let mut state = self.nodes[self.root].state.box_clone();
+        for (position, &action) in actions.iter().enumerate() {
+            let legal = if state.is_terminal() {
+                false
+            } else if state.is_chance_node() {
+                state
+                    .chance_outcomes()
+                    .iter()
+                    .any(|&(outcome, _)| outcome == action)
+            } else {
+                state.get_legal_moves().contains(&action)
+            };
+            if !legal {
+                return Err(MctsError::IllegalMove(position));
+            }
+            state.make_move(action);
+        }
+        for &action in actions {
+            self.advance_root(action);
+        }
+        Ok(())
+    }
+
+    /// Replaces the tree with a copy of the subtree that `other` grew below
+    /// its root move `action`, so that a search can start from statistics
+    /// gathered by another engine, for example in an earlier game that
+    /// reached the same position. Nothing changes, and `false` is returned,
+    /// if `other` never expanded `action` or [`GameState::same_state`] does
+    /// not confirm that it leads to this engine's root position.
+    pub fn graft(&mut self, other: &MCTS<A>, action: A) -> bool {
+        let source = other.nodes[other.root]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| other.nodes[child].last_action == Some(action));
+        let Some(source) = source else {
+            return false;
+        };
+        let root_state = self.nodes[self.root].state.as_ref();
+        if !root_state.same_state(other.nodes[source].state.as_ref()) {
+            return false;
+        }
+
+        // Only the nodes below `source` are copied. Transposed nodes are
+        // reachable from several parents, so remember where each one went.
+        let capacity = self.nodes.capacity();
+        let mut new_indices: BTreeMap<NodeIndex, NodeIndex> = BTreeMap::new();
+        let mut nodes: Vec<Node<A>> = Vec::with_capacity(capacity);
+        let mut wins = Vec::with_capacity(capacity);
+        let mut visits = Vec::with_capacity(capacity);
+        let mut queue = VecDeque::from([(source, None::<NodeIndex>)]);
+
+        while let Some((old_index, parent)) = queue.pop_front() {
+            if let Some(&new_index) = new_indices.get(&old_index) {
+                if let Some(parent) = parent {
+                    nodes[parent].children.push(new_index);
+                }
+                continue;
+            }
+
+            let mut node = other.nodes[old_index].clone();
+            let new_index = nodes.len();
+            new_indices.insert(old_index, new_index);
+            for child in core::mem::take(&mut node.children) {
+                queue.push_back((child, Some(new_index)));
+            }
+            node.parent = parent;
+            nodes.push(node);
+            wins.push(other.wins[old_index]);
+            visits.push(other.visits[old_index]);
+            if let Some(parent) = parent {
+                nodes[parent].children.push(new_index);
+            }
+        }
+
+        self.transpositions = other
+            .transpositions
+            .iter()
+            .filter_map(|(&key, index)| new_indices.get(index).map(|&new_index| (key, new_index)))
+            .collect();
+        self.nodes = nodes;
+        self.wins = wins;
+        self.visits = visits;
+        self.root = 0;
+        true
+    }
+
+    /// Discards the whole tree and starts over from `state`, keeping the
+    /// configuration and RNG of the engine. The node storage is reused, so
+    /// this is cheaper than building a new engine for every search. The
+    /// simulation and reuse counters start again from zero.
+    pub fn reset(&mut self, state: Box<dyn GameState<Action = A>>) {
+        self.rollouts = 0;
+        self.rollout_plies = 0;
+        self.nodes_reused = 0;
+        let num_players = state.num_players();
+        let player_just_moved = (state.current_player() + num_players - 1) % num_players;
+        self.install_root(state, None, player_just_moved);
+    }
+
+    /// Replaces the tree with a single root holding `state`.
+    fn install_root(
+        &mut self,
+        state: Box<dyn GameState<Action = A>>,
+        last_action: Option<A>,
+        player_just_moved: usize,
+    ) {
+        self.transpositions.clear();
+        if let Some(key) = state.state_key() {
+            self.transpositions.insert(key, 0);
+        }
+        self.nodes.clear();
+        self.wins.clear();
+        self.visits.clear();
+        self.push_node(Node::new(state, None, last_action, player_just_moved));
+        self.root = 0;
+    }
+
+    /// Removes every subtree whose root has fewer than `threshold` visits,
+    /// so that long-running engines can bound their memory without
+    /// discarding the whole tree. The moves of removed children are made
+    /// untried again, and the nodes on the principal variation are always
+    /// kept, so the best move does not change. Visits of removed nodes still
+    /// count towards their parents.
+    pub fn prune_below_visits(&mut self, threshold: f64) {
+        let mut keep: Vec<bool> = self
+            .visits
+            .iter()
+            .map(|&visits| visits >= threshold)
+            .collect();
+        let mut current = self.root;
+        keep[current] = true;
+        while let Some(child) = self.most_visited_child(current) {
+            keep[child] = true;
+            current = child;
+        }
+        self.retain_subtree(self.root, |index| keep[index]);
+    }
+
+    /// Limits the search at the root to the moves in `allowed`, for
+    /// analysing a few candidate moves. Children for other moves are
+    /// removed along with their subtrees. The restriction lasts until the
+    /// root changes, and can only be narrowed further in the meantime.
+    ///
+    /// Nothing changes, and `false` is returned, if `allowed` is empty or
+    /// holds a move that the root cannot play.
+    pub fn restrict_root_moves(&mut self, allowed: &[A]) -> bool {
+        let root = &self.nodes[self.root];
+        let playable = |action: &A| {
+            root.untried_actions.contains(action)
+                || root
+                    .children
+                    .iter()
+                    .any(|&child| self.nodes[child].last_action == Some(*action))
+        };
+        if allowed.is_empty() || !allowed.iter().all(playable) {
+            return false;
+        }
+
+        let mut keep = vec![true; self.nodes.len()];
+        for &child in &root.children {
+            keep[child] = self.nodes[child]
+                .last_action
+                .is_some_and(|action| allowed.contains(&action));
+        }
+        self.retain_subtree(self.root, |index| keep[index]);
+        self.nodes[self.root]
+            .untried_actions
+            .retain(|action| allowed.contains(action));
+        true
+    }
+
+    /// Compacts `nodes` so that it only holds the subtree rooted at
+    /// `new_root`, which becomes index 0. Children for which `keep` is false
+    /// are dropped along with their subtrees, and their moves are returned
+    /// to the untried actions of their parents.
+    fn retain_subtree(&mut self, new_root: NodeIndex, keep: impl Fn(NodeIndex) -> bool) {
+        let capacity = self.nodes.capacity();
+        let mut old_nodes: Vec<Option<Node<A>>> = core::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(Some)
+            .collect();
+        // Transposed nodes are reachable from several parents, so remember
+        // where each retained node ended up.
+        let mut new_indices: BTreeMap<NodeIndex, NodeIndex> = BTreeMap::new();
+        let mut nodes: Vec<Node<A>> = Vec::with_capacity(capacity);
+        let mut wins = Vec::with_capacity(capacity);
+        let mut visits = Vec::with_capacity(capacity);
+        let mut queue = VecDeque::from([(new_root, None::<NodeIndex>)]);
+
+        while let Some((old_index, parent)) = queue.pop_front() {
+            if let Some(&new_index) = new_indices.get(&old_index) {
+                if let Some(parent) = parent {
+                    nodes[parent].children.push(new_index);
+                }
+                continue;
+            }
+
+            let mut node = old_nodes[old_index].take().unwrap();
+            let new_index = nodes.len();
+            new_indices.insert(old_index, new_index);
+            let mut pruned = false;
+            for child in core::mem::take(&mut node.children) {
+                if keep(child) {
+                    queue.push_back((child, Some(new_index)));
+                } else if let Some(action) = old_nodes[child].as_ref().and_then(|c| c.last_action) {
+                    node.untried_actions.push(action);
+                    pruned = true;
+                }
+            }
+            // Expansion pops the most likely or most promising move off the
+            // end, so returned moves have to be put back in order.
+            if pruned {
+                if let Some(priors) = &node.priors {
+                    sort_by_prior(&mut node.untried_actions, priors);
+                } else if node.ordered {
+                    node.state.order_moves(&mut node.untried_actions);
+                }
+            }
+            node.parent = parent;
+            nodes.push(node);
+            wins.push(self.wins[old_index]);
+            visits.push(self.visits[old_index]);
+            if let Some(parent) = parent {
+                nodes[parent].children.push(new_index);
+            }
+        }
+
+        self.transpositions
+            .retain(|_, index| match new_indices.get(index) {
+                Some(&new_index) => {
+                    *index = new_index;
+                    true
+                }
+                None => false,
+            });
+        self.nodes = nodes;
+        self.wins = wins;
+        self.visits = visits;
+        self.root = 0;
+    }
+
+    /// Scores `state` for the player to move in it, the way a new leaf would
+    /// be scored: with the leaf evaluator if one is set, or else with
+    /// [`MCTS::set_rollouts_per_leaf`] rollouts of the rollout policy. A
+    /// terminal `state` gets its terminal reward. The tree is left untouched,
+    /// which makes this a building block for flat Monte Carlo and other
+    /// search control outside the engine.
+    pub fn simulate_state(&mut self, state: &dyn GameState<Action = A>) -> Result<f64, MctsError> {
+        let player = state.current_player();
+        let outcome = if state.is_terminal() {
+            let outcome = self.terminal_outcome(state, state.get_winner())?;
+            self.finish_rollout(outcome, 0, 0)
+        } else {
+            self.simulate_from(state.box_clone(), 0)?
+        };
+        Ok(self.reward_scale.rescale(outcome.reward_for(player)))
+    }
+
+    /// Evaluates every root move with `rollouts_per_move` rollouts from the
+    /// position it leads to, without building a tree, and returns the move
+    /// with the best average reward. Useful as a baseline for comparing
+    /// against the full search.
+    pub fn get_best_move_flat(&mut self, rollouts_per_move: u32) -> Result<A, MctsError> {
+        self.check_root()?;
+        let root_state = self.nodes[self.root].state.box_clone();
+        let mover = root_state.current_player();
+
+        let mut best: Option<(A, f64)> = None;
+        for action in root_state.get_legal_moves() {
+            let child_state = root_state.apply(action);
+            let mut state = child_state.box_clone();
+            let mut total = 0.0;
+            for rollout in 0..rollouts_per_move {
+                if rollout > 0 && !self.undo_rollout(state.as_mut()) {
+                    state = child_state.box_clone();
+                }
+                total += self.rollout(state.as_mut(), 1)?.reward_for(mover);
+            }
+            if best.is_none_or(|(_, best_total)| total > best_total) {
+                best = Some((action, total));
+            }
+        }
+        best.map(|(action, _)| action)
+            .ok_or(MctsError::NoLegalMoves)
+    }
+
+    /// Runs `iterations` iterations of information set MCTS for games with
+    /// hidden information, and returns the most visited root move.
+    ///
+    /// Every iteration searches a fresh [`GameState::determinize`] sample of
+    /// the root, and all samples share one tree whose nodes stand for the
+    /// moves played rather than for states. The engine therefore never relies
+    /// on hidden information held in the root state. The states stored in
+    /// the tree are samples, so after a move the engine should be
+    /// [`MCTS::reset`] with the real state rather than advanced.
+    pub fn get_best_move_determinized(&mut self, iterations: u32) -> Result<A, MctsError> {
+        self.check_root()?;
+        if let Some(action) = self.forced_move() {
+            return Ok(action);
+        }
+        for _ in 0..iterations {
+            self.run_determinized_iteration()?;
+        }
+        self.best_action()
+    }
+
+    /// Searches until `budget` has elapsed and returns the most visited move.
+    ///
+    /// The clock is only checked every 64 iterations, so the search may run
+    /// slightly past the deadline. The first 64 always run, so that even a
+    /// budget too small for a single iteration has a move to return.
+    #[cfg(feature = "std")]
+    pub fn get_best_move_timed(&mut self, budget: Duration) -> Result<A, MctsError> {
+        self.check_root()?;
+        if let Some(action) = self.forced_move() {
+            return Ok(action);
+        }
+        let deadline = Instant::now() + budget;
+        'search: loop {
+            for _ in 0..TIME_CHECK_INTERVAL {
+                if !self.run_iteration()? {
+                    break 'search;
+                }
+            }
+            if Instant::now() >= deadline {
+                break;
+            }
+        }
+
+        self.best_action()
+    }
+
+    /// Searches until `stop` is set, typically from another thread, and
+    /// returns the most visited move. Also returns once the solver has
+    /// proven the root.
+    ///
+    /// Like the timed search, the flag is only checked every 64 iterations.
+    pub fn get_best_move_until(&mut self, stop: &AtomicBool) -> Result<A, MctsError> {
+        self.check_root()?;
+        if let Some(action) = self.forced_move() {
+            return Ok(action);
+        }
+        // Run at least one batch, so that there is a move to return even if
+        // `stop` was set before the call.
+        'search: loop {
+            for _ in 0..TIME_CHECK_INTERVAL {
+                if !self.run_iteration()? {
+                    break 'search;
+                }
+            }
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+
+        self.best_action()
+    }
+
+    /// Keeps searching the current tree until `stop` is set, so that the
+    /// opponent's thinking time is not wasted. Also returns once the solver
+    /// has proven the root.
+    ///
+    /// After making a move, call [`MCTS::advance_root`] with it and ponder
+    /// on another thread or between polls while the opponent thinks. Once
+    /// the opponent has moved, set `stop`, call `advance_root` with their
+    /// move and search as usual: the subtree of that move is kept, so the
+    /// search starts warm. The flag is checked every 64 iterations.
+    pub fn ponder(&mut self, stop: &AtomicBool) -> Result<(), MctsError> {
+        self.check_root()?;
+        while !stop.load(Ordering::Relaxed) {
+            for _ in 0..TIME_CHECK_INTERVAL {
+                if !self.run_iteration()? {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MCTS<usize> {
+    /// Lays the visits of the root's moves out as `height` rows of `width`
+    /// cells, for games whose moves are cell indices `row * width + col`.
+    /// Cells without an expanded move are `0.0`, and moves outside the grid,
+    /// such as a pass, are left out.
+    pub fn to_visit_grid(&self, width: usize, height: usize) -> Vec<Vec<f64>> {
+        let mut grid = vec![vec![0.0; width]; height];
+        for (action, visits) in self.root_action_visits() {
+            if action < width * height {
+                grid[action / width][action % width] = visits;
+            }
+        }
+        grid
+    }
+}
+
+/// Plays `state` out with random moves for [`MCTS::get_best_move_parallel`],
+/// stopping after `max_depth` moves if set. Chance outcomes are sampled by
+/// their probabilities.
+#[cfg(feature = "parallel")]
+fn random_playout<A: Copy + Eq>(
+    mut state: Box<dyn GameState<Action = A>>,
+    seed: u64,
+    max_depth: Option<usize>,
+    record_moves: bool,
+) -> Playout<A> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut moves = Vec::new();
+    let mut legal_moves = Vec::new();
+    let mut depth = 0;
+    let mut cut_off = false;
+    let mut stuck = false;
+    while !state.is_terminal() {
+        if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            cut_off = true;
+            break;
+        }
+        depth += 1;
+
+        if state.is_chance_node() {
+            match sample_outcome(&state.chance_outcomes(), &mut rng) {
+                Some(action) => state.make_move(action),
+                None => {
+                    stuck = true;
+                    break;
+                }
+            }
+            continue;
+        }
+        legal_moves.clear();
+        state.legal_moves_into(&mut legal_moves);
+        if legal_moves.is_empty() {
+            stuck = true;
+            break;
+        }
+        let action = legal_moves[rng.gen_range(0..legal_moves.len())];
+        if record_moves {
+            moves.push((state.current_player(), action));
+        }
+        state.make_move(action);
+    }
+    Playout {
+        state,
+        cut_off,
+        stuck,
+        depth,
+        moves,
+    }
+}
+
+impl<A: Copy + Eq + Send> MCTS<A> {
+    /// Runs `threads` independent searches of `iterations` iterations each,
+    /// every one on its own thread with its own entropy-seeded RNG, and
+    /// returns the move with the most visits summed over all of them.
+    ///
+    /// `state_fn` builds the root state for each search. Like
+    /// [`MCTS::get_best_move_parallel`], this uses scoped `std` threads
+    /// rather than rayon: each search is a single long-running task, which
+    /// leaves a work-stealing pool nothing to balance.
+    #[cfg(feature = "std")]
+    pub fn get_best_move_root_parallel(
+        state_fn: impl Fn() -> Box<dyn GameState<Action = A>> + Sync,
+        iterations: u32,
+        threads: usize,
+    ) -> Result<A, MctsError> {
+        let results: Vec<Result<Vec<(A, f64)>, MctsError>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut mcts = MCTS::new(state_fn());
+                        let action = mcts.get_best_move(iterations)?;
+                        let statistics = mcts.move_statistics();
+                        // A forced move is returned without searching.
+                        if statistics.is_empty() {
+                            return Ok(vec![(action, 0.0)]);
+                        }
+                        Ok(statistics
+                            .into_iter()
+                            .map(|(action, visits, _)| (action, visits))
+                            .collect())
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("search thread panicked"))
+                .collect()
+        });
+
+        let mut merged: Vec<(A, f64)> = Vec::new();
+        for statistics in results {
+            for (action, visits) in statistics? {
+                match merged
+                    .iter_mut()
+                    .find(|(merged_action, _)| *merged_action == action)
+                {
+                    Some((_, total)) => *total += visits,
+                    None => merged.push((action, visits)),
+                }
+            }
+        }
+
+        merged
+            .into_iter()
+            .max_by(|a, b| compare_values(a.1, b.1))
+            .map(|(action, _)| action)
+            .ok_or(MctsError::NoLegalMoves)
+    }
+
+    /// Like [`MCTS::get_best_move`], but rolls every new leaf out `threads`
+    /// times at once, each rollout on its own thread, and backpropagates
+    /// their average reward. The tree is still grown by a single thread, so
+    /// this pays off when rollouts are much more expensive than selection.
+    ///
+    /// The rollouts run on scoped `std` threads fed over channels instead of
+    /// a rayon pool: the threads are started once and kept for the whole
+    /// search, each taking one rollout per leaf, so there is no work for a
+    /// work-stealing pool to balance.
+    ///
+    /// The threads play random moves, since the rollout policy of the engine
+    /// cannot be shared between them. Their RNGs are seeded from the engine
+    /// RNG, so a seeded search is reproducible.
+    #[cfg(feature = "parallel")]
+    pub fn get_best_move_parallel(
+        &mut self,
+        iterations: u32,
+        threads: usize,
+    ) -> Result<A, MctsError> {
+        self.check_root()?;
+        let max_depth = self.max_rollout_depth;
+        let record_moves = self.rave.is_some();
+        std::thread::scope(|scope| {
+            let (result_sender, results) = mpsc::channel();
+            let jobs = (0..threads.max(1))
+                .map(|_| {
+                    let (job_sender, job_receiver) = mpsc::channel::<RolloutJob<A>>();
+                    let result_sender = result_sender.clone();
+                    scope.spawn(move || {
+                        for (index, state, seed) in job_receiver {
+                            let playout = random_playout(state, seed, max_depth, record_moves);
+                            if result_sender.send((index, playout)).is_err() {
+                                break;
+                            }
+                        }
+                    });
+                    job_sender
+                })
+                .collect();
+            self.workers = Some(RolloutWorkers { jobs, results });
+            let best = self.get_best_move(iterations);
+            // Closing the job channels lets the threads finish.
+            self.workers = None;
+            best
+        })
+    }
+}
+
+/// Draws one of `outcomes` in proportion to its probability, or `None` if
+/// there are none.
+fn sample_outcome<A: Copy>(outcomes: &[(A, f64)], rng: &mut StdRng) -> Option<A> {
+    let total: f64 = outcomes.iter().map(|&(_, probability)| probability).sum();
+    let mut threshold = rng.gen::<f64>() * total;
+    for &(action, probability) in outcomes {
+        if threshold < probability {
+            return Some(action);
+        }
+        threshold -= probability;
+    }
+    outcomes.last().map(|&(action, _)| action)
+}
+
+/// Orders `a` and `b` like `partial_cmp`, but with NaN below every number,
+/// so that a NaN value is never the maximum and cannot cause a panic.
+fn compare_values(a: f64, b: f64) -> core::cmp::Ordering {
+    a.partial_cmp(&b)
+        .unwrap_or_else(|| b.is_nan().cmp(&a.is_nan()))
+}
+
+/// Whether following children from some node of `nodes` leads back to it.
+/// Walks the tree with an explicit stack, so that deep trees cannot
+/// overflow the call stack.
+#[cfg(any(feature = "std", test))]
+fn has_cycle<A>(nodes: &[Node<A>]) -> bool {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Unvisited,
+        OnStack,
+        Done,
+    }
+    let mut marks = vec![Mark::Unvisited; nodes.len()];
+    // Each entry is a node and the position of its next child to visit.
+    let mut stack: Vec<(NodeIndex, usize)> = Vec::new();
+    for start in 0..nodes.len() {
+        if marks[start] != Mark::Unvisited {
+            continue;
+        }
+        marks[start] = Mark::OnStack;
+        stack.push((start, 0));
+        while let Some(&(node, next)) = stack.last() {
+            match nodes[node].children.get(next) {
+                Some(&child) => {
+                    stack.last_mut().unwrap().1 += 1;
+                    match marks[child] {
+                        Mark::Unvisited => {
+                            marks[child] = Mark::OnStack;
+                            stack.push((child, 0));
+                        }
+                        Mark::OnStack => return true,
+                        Mark::Done => {}
+                    }
+                }
+                None => {
+                    marks[node] = Mark::Done;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    false
+}
+
+fn prior_of<A: Copy + Eq>(priors: &[(A, f64)], action: A) -> f64 {
+    priors
+        .iter()
+        .find(|&&(candidate, _)| candidate == action)
+        .map_or(0.0, |&(_, prior)| prior)
+}
+
+/// Sorts `actions` by increasing prior, so that the most likely move is last.
+fn sort_by_prior<A: Copy + Eq>(actions: &mut [A], priors: &[(A, f64)]) {
+    actions.sort_by(|&a, &b| compare_values(prior_of(priors, a), prior_of(priors, b)));
+}
+
+/// Draws a sample from a symmetric Dirichlet distribution with `len`
+/// components, by normalising independent Gamma(`alpha`) draws.
+fn sample_dirichlet(rng: &mut StdRng, alpha: f64, len: usize) -> Vec<f64> {
+    let samples: Vec<f64> = (0..len).map(|_| sample_gamma(rng, alpha)).collect();
+    let total: f64 = samples.iter().sum();
+    if total > 0.0 {
+        samples.into_iter().map(|sample| sample / total).collect()
+    } else {
+        vec![1.0 / len as f64; len]
+    }
+}
+
+/// Marsaglia and Tsang's method for Gamma(`shape`, 1), boosted for shapes
+/// below one.
+fn sample_gamma(rng: &mut StdRng, shape: f64) -> f64 {
+    if shape < 1.0 {
+        let boost = rng.gen::<f64>().powf(1.0 / shape);
+        return sample_gamma(rng, shape + 1.0) * boost;
+    }
+
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = sample_standard_normal(rng);
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u: f64 = rng.gen();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+/// Box-Muller transform.
+fn sample_standard_normal(rng: &mut StdRng) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * core::f64::consts::PI * u2).cos()
+}
+
+#[cfg(feature = "std")]
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(feature = "std")]
+fn write_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(feature = "std")]
+fn write_optional_u64(out: &mut Vec<u8>, value: Option<u64>) {
+    write_u64(out, value.unwrap_or(u64::MAX));
+}
+
+#[cfg(feature = "std")]
+fn write_f64(out: &mut Vec<u8>, value: f64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(feature = "std")]
+fn read_u64(input: &mut &[u8]) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(feature = "std")]
+fn read_optional_u64(input: &mut &[u8]) -> io::Result<Option<u64>> {
+    read_u64(input).map(|value| (value != u64::MAX).then_some(value))
+}
+
+#[cfg(feature = "std")]
+fn read_f64(input: &mut &[u8]) -> io::Result<f64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rollout::{DecisiveRollout, GreedyRollout, PerPlayerRollout};
+    use rand::{Rng, RngCore};
+    use std::time::{Duration, Instant};
+
+    const LINES: [[usize; 3]; 8] = [
+        [0, 1, 2],
+        [3, 4, 5],
+        [6, 7, 8],
+        [0, 3, 6],
+        [1, 4, 7],
+        [2, 5, 8],
+        [0, 4, 8],
+        [2, 4, 6],
+    ];
+
+    /// TicTacToe with X as player `0`, who moves first, and O as player `1`.
+    #[derive(Clone, PartialEq)]
+    struct TicTacToe {
+        board: [Option<usize>; 9],
+        current_player: usize,
+        // A cell that the optional hooks of the game recommend.
+        favoured: Option<usize>,
+    }
+
+    impl TicTacToe {
+        fn new() -> Self {
+            TicTacToe::from_board(".........")
+        }
+
+        /// Reads a board from nine cells of `X`, `O` or `.`, row by row. The
+        /// player with fewer marks is to move, X on a tie.
+        fn from_board(cells: &str) -> Self {
+            let mut board = [None; 9];
+            for (cell, mark) in board.iter_mut().zip(cells.chars()) {
+                *cell = match mark {
+                    'X' => Some(0),
+                    'O' => Some(1),
+                    _ => None,
+                };
+            }
+            let marks = |player| board.iter().filter(|&&cell| cell == Some(player)).count();
+            let current_player = if marks(0) > marks(1) { 1 } else { 0 };
+            TicTacToe {
+                board,
+                current_player,
+                favoured: None,
+            }
+        }
+
+        fn favouring(mut self, cell: usize) -> Self {
+            self.favoured = Some(cell);
+            self
+        }
+
+        /// An empty cell that completes a line of `player`'s.
+        fn completing_cell(&self, player: usize) -> Option<usize> {
+            LINES.iter().find_map(|line| {
+                let empty: Vec<usize> = line
+                    .iter()
+                    .copied()
+                    .filter(|&cell| self.board[cell].is_none())
+                    .collect();
+                let owned = line
+                    .iter()
+                    .filter(|&&cell| self.board[cell] == Some(player))
+                    .count();
+                (owned == 2 && empty.len() == 1).then(|| empty[0])
+            })
+        }
+
+        /// Reads a state written by `serialize_state`.
+        #[cfg(feature = "std")]
+        fn deserialize(bytes: &[u8]) -> Option<Box<dyn GameState<Action = usize>>> {
+            let (&current_player, cells) = bytes.split_last()?;
+            if cells.len() != 9 {
+                return None;
+            }
+            let mut state = TicTacToe::new();
+            for (cell, &mark) in state.board.iter_mut().zip(cells) {
+                *cell = mark.checked_sub(1).map(usize::from);
+            }
+            state.current_player = usize::from(current_player);
+            Some(Box::new(state))
+        }
+    }
+
+    impl GameState for TicTacToe {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.is_terminal() {
+                return Vec::new();
+            }
+            (0..9).filter(|&cell| self.board[cell].is_none()).collect()
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.board[action] = Some(self.current_player);
+            self.current_player = 1 - self.current_player;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.get_winner().is_some()
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            for [a, b, c] in LINES {
+                if self.board[a].is_some()
+                    && self.board[a] == self.board[b]
+                    && self.board[b] == self.board[c]
+                {
+                    return self.board[a].map(GameResult::Win);
+                }
+            }
+            if self.board.iter().all(Option::is_some) {
+                return Some(GameResult::Draw);
+            }
+            None
+        }
+
+        fn current_player(&self) -> usize {
+            self.current_player
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        fn serialize_state(&self) -> Option<Vec<u8>> {
+            let cells = self
+                .board
+                .iter()
+                .map(|cell| cell.map_or(0, |player| player as u8 + 1));
+            Some(cells.chain([self.current_player as u8]).collect())
+        }
+
+        /// Gives the favoured cell 90% of the prior.
+        fn get_priors(&self) -> Vec<(usize, f64)> {
+            let moves = self.get_legal_moves();
+            let favoured = self.favoured.filter(|cell| moves.contains(cell));
+            let others = (moves.len() - favoured.is_some() as usize) as f64;
+            let share = if favoured.is_some() { 0.1 } else { 1.0 };
+            moves
+                .into_iter()
+                .map(|cell| match favoured {
+                    Some(favoured) if favoured == cell => (cell, 0.9),
+                    _ => (cell, share / others),
+                })
+                .collect()
+        }
+
+        fn winning_move(&self) -> Option<usize> {
+            self.completing_cell(self.current_player)
+        }
+
+        fn must_block(&self) -> Option<usize> {
+            self.completing_cell(1 - self.current_player)
+        }
+
+        /// Rates holding the favoured cell as a sure win.
+        fn heuristic_value(&self) -> f64 {
+            let just_moved = 1 - self.current_player;
+            let holds = self
+                .favoured
+                .is_some_and(|cell| self.board[cell] == Some(just_moved));
+            if holds {
+                1.0
+            } else {
+                0.0
+            }
+        }
+    }
+
+    /// An engine whose root is the empty TicTacToe board with one child per
+    /// `(action, wins, visits)` and no untried moves, so that selection
+    /// always descends into one of the children.
+    fn hand_built(children: &[(usize, f64, f64)]) -> MCTS<usize> {
+        let root_state = TicTacToe::new();
+        let mut mcts = MCTS::new(root_state.box_clone());
+        mcts.nodes[0].untried_actions.clear();
+        mcts.visits[0] = 1.0;
+        for &(action, wins, visits) in children {
+            let mut state = root_state.box_clone();
+            state.make_move(action);
+            let index = mcts.push_node(Node::new(state, Some(0), Some(action), 0));
+            mcts.wins[index] = wins;
+            mcts.visits[index] = visits;
+            mcts.nodes[0].children.push(index);
+            mcts.wins[0] += visits - wins;
+            mcts.visits[0] += visits;
+        }
+        mcts
+    }
+
+    #[test]
+    fn exploration_constant_trades_off_visits_and_value() {
+        let children = [(0, 9.0, 10.0), (1, 1.0, 2.0), (2, 3.0, 5.0)];
+
+        let mut mcts = hand_built(&children);
+        mcts.exploration_constant = 1000.0;
+        let least_visited = mcts.select(0);
+        assert_eq!(mcts.nodes[least_visited].last_action, Some(1));
+
+        let mut mcts = hand_built(&children);
+        mcts.exploration_constant = 0.0;
+        let greedy = mcts.select(0);
+        assert_eq!(mcts.nodes[greedy].last_action, Some(0));
+    }
+
+    fn best_move_for(cells: &str, iterations: u32, seed: u64) -> usize {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::from_board(cells)), seed);
+        mcts.get_best_move(iterations).unwrap()
+    }
+
+    #[test]
+    fn both_players_block_a_threatened_line() {
+        for seed in 0..5 {
+            // X threatens the top row, O must take its last cell.
+            assert_eq!(best_move_for("XX.O.....", 3000, seed), 2);
+            // O threatens the middle column, X must take its last cell.
+            assert_eq!(best_move_for("XO..O...X", 3000, seed), 7);
+        }
+    }
+
+    #[test]
+    fn each_node_is_credited_with_the_reward_of_its_mover() {
+        // X has taken a corner; below it O can take the centre or an edge.
+        let mut mcts = hand_built(&[(0, 0.0, 0.0)]);
+        mcts.wins[0] = 0.0;
+        mcts.visits[0] = 0.0;
+        mcts.nodes[1].untried_actions.clear();
+        for action in [4, 1] {
+            let mut state = mcts.nodes[1].state.clone();
+            state.make_move(action);
+            let index = mcts.push_node(Node::new(state, Some(1), Some(action), 1));
+            mcts.nodes[1].children.push(index);
+        }
+
+        // The rollouts through the centre are won by O, those through the
+        // edge by X.
+        for _ in 0..3 {
+            mcts.path = vec![0, 1, 2];
+            mcts.backpropagate(Outcome::Rewards(vec![0.0, 1.0]));
+            mcts.path = vec![0, 1, 3];
+            mcts.backpropagate(Outcome::Rewards(vec![1.0, 0.0]));
+        }
+        assert_eq!(mcts.wins[2], 3.0);
+        assert_eq!(mcts.wins[3], 0.0);
+        assert_eq!(mcts.wins[1], 3.0);
+        assert_eq!(mcts.wins[0], 3.0);
+
+        // O picks the centre, which wins for O, not the edge that X likes.
+        mcts.exploration_constant = 0.0;
+        let selected = mcts.select(0);
+        assert_eq!(mcts.nodes[selected].last_action, Some(4));
+    }
+
+    /// A game with a single move per turn that ends after `remaining` moves,
+    /// won by whoever makes the last one.
+    #[derive(Clone)]
+    struct Corridor {
+        remaining: usize,
+        current_player: usize,
+    }
+
+    impl GameState for Corridor {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.remaining == 0 {
+                Vec::new()
+            } else {
+                vec![0]
+            }
+        }
+
+        fn make_move(&mut self, _action: usize) {
+            self.remaining -= 1;
+            self.current_player = 1 - self.current_player;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.remaining == 0
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.is_terminal()
+                .then_some(GameResult::Win(1 - self.current_player))
+        }
+
+        fn current_player(&self) -> usize {
+            self.current_player
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn deep_linear_tree_does_not_overflow_the_stack() {
+        const DEPTH: usize = 300_000;
+        let state = Corridor {
+            remaining: DEPTH,
+            current_player: 0,
+        };
+        let mut mcts = MCTS::new(Box::new(state));
+        for index in 1..=DEPTH {
+            let mut state = mcts.nodes[index - 1].state.clone();
+            let mover = state.current_player();
+            state.make_move(0);
+            mcts.nodes[index - 1].untried_actions.clear();
+            mcts.nodes[index - 1].children.push(index);
+            mcts.push_node(Node::new(state, Some(index - 1), Some(0), mover));
+        }
+        mcts.wins.fill(0.5);
+        mcts.visits.fill(1.0);
+
+        let leaf = mcts.select(0);
+        assert_eq!(leaf, DEPTH);
+        mcts.backpropagate(Outcome::Rewards(vec![1.0, 0.0]));
+        assert_eq!(mcts.visits[DEPTH], 2.0);
+        assert_eq!(mcts.visits[0], 2.0);
+    }
+
+    /// A game of `depth` turns in which every turn picks one of `width`
+    /// numbers. The first player wins if the picks add up to an even number.
+    #[derive(Clone)]
+    struct Wide {
+        width: usize,
+        depth: usize,
+        picks: Vec<usize>,
+    }
+
+    impl Wide {
+        fn new(width: usize, depth: usize) -> Self {
+            Wide {
+                width,
+                depth,
+                picks: Vec::new(),
+            }
+        }
+    }
+
+    impl GameState for Wide {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.is_terminal() {
+                Vec::new()
+            } else {
+                (0..self.width).collect()
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.picks.push(action);
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.picks.len() == self.depth
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            let sum: usize = self.picks.iter().sum();
+            self.is_terminal().then_some(GameResult::Win(sum % 2))
+        }
+
+        fn current_player(&self) -> usize {
+            self.picks.len() % 2
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn timed_search_stops_near_the_deadline() {
+        // Far too many moves to expand them all within the budget.
+        let mut mcts = MCTS::new(Box::new(Wide::new(1000, 6)));
+        let budget = Duration::from_millis(50);
+        let start = Instant::now();
+        let action = mcts.get_best_move_timed(budget).unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(elapsed >= budget);
+        assert!(elapsed < budget * 10, "took {:?}", elapsed);
+        assert!(action < 1000);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn timed_search_without_a_budget_still_returns_a_move() {
+        let mut mcts = MCTS::new(Box::new(TicTacToe::new()));
+        let action = mcts.get_best_move_timed(Duration::ZERO).unwrap();
+        assert!(TicTacToe::new().get_legal_moves().contains(&action));
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_search() {
+        let statistics = |mcts: &MCTS<usize>| -> Vec<(Option<usize>, f64, f64)> {
+            (0..mcts.node_count())
+                .map(|index| {
+                    (
+                        mcts.nodes[index].last_action,
+                        mcts.wins[index],
+                        mcts.visits[index],
+                    )
+                })
+                .collect()
+        };
+        let mut first = MCTS::with_seed(Box::new(TicTacToe::new()), 42);
+        let mut second = MCTS::with_seed(Box::new(TicTacToe::new()), 42);
+        assert_eq!(first.get_best_move(500), second.get_best_move(500));
+        assert_eq!(statistics(&first), statistics(&second));
+    }
+
+    #[test]
+    fn finished_boards_are_reported_as_errors() {
+        let full = TicTacToe::from_board("XOXXOOOXX");
+        let won = TicTacToe::from_board("XXXOO....");
+        for state in [full, won] {
+            let mut mcts = MCTS::with_seed(Box::new(state), 0);
+            assert_eq!(mcts.get_best_move(100), Err(MctsError::TerminalRoot));
+        }
+
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        assert_eq!(mcts.get_best_move(0), Err(MctsError::NoLegalMoves));
+    }
+
+    /// A game that ends after one of two moves and reports a player who does
+    /// not exist.
+    #[derive(Clone)]
+    struct MissingWinner {
+        over: bool,
+    }
+
+    impl GameState for MissingWinner {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.over {
+                Vec::new()
+            } else {
+                vec![0, 1]
+            }
+        }
+
+        fn make_move(&mut self, _action: usize) {
+            self.over = true;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.over
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.over.then_some(GameResult::Win(7))
+        }
+
+        fn current_player(&self) -> usize {
+            0
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn unknown_winner_is_an_error() {
+        let mut mcts = MCTS::with_seed(Box::new(MissingWinner { over: false }), 0);
+        assert_eq!(
+            mcts.get_best_move(10),
+            Err(MctsError::InvalidWinner(Some(GameResult::Win(7))))
+        );
+    }
+
+    #[test]
+    fn move_statistics_favour_the_centre() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 3);
+        assert!(mcts.move_statistics().is_empty());
+        mcts.get_best_move(5000).unwrap();
+
+        let statistics = mcts.move_statistics();
+        assert_eq!(statistics.len(), 9);
+        assert!(statistics.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(statistics[0].0, 4);
+        let total: f64 = statistics.iter().map(|&(_, visits, _)| visits).sum();
+        assert!(statistics[0].1 / total > 0.2);
+    }
+
+    #[test]
+    fn final_move_policies_can_disagree() {
+        let children = [(0, 5.0, 10.0), (1, 3.0, 3.0), (2, 1.0, 4.0), (3, 0.0, 0.0)];
+        let mut mcts = hand_built(&children);
+        let mut pick = |policy| mcts.get_best_move_with(0, policy).unwrap();
+        assert_eq!(pick(FinalMoveSelection::MostVisits), 0);
+        assert_eq!(pick(FinalMoveSelection::HighestValue), 1);
+        // Without iterations left to settle the disagreement, the robust
+        // child falls back to the most visited one.
+        assert_eq!(pick(FinalMoveSelection::Robust), 0);
+
+        // A child that is both the most visited and the best valued is the
+        // robust child.
+        let mut mcts = hand_built(&[(0, 9.0, 10.0), (1, 2.0, 4.0), (2, 1.0, 3.0)]);
+        for policy in [
+            FinalMoveSelection::MostVisits,
+            FinalMoveSelection::HighestValue,
+            FinalMoveSelection::Robust,
+        ] {
+            assert_eq!(mcts.get_best_move_with(0, policy), Ok(0));
+        }
+    }
+
+    /// The `(action, visits, wins)` of every child of `index`, most visited
+    /// first.
+    fn child_statistics(mcts: &MCTS<usize>, index: NodeIndex) -> Vec<(usize, f64, f64)> {
+        let mut statistics: Vec<(usize, f64, f64)> = mcts.nodes[index]
+            .children
+            .iter()
+            .map(|&child| {
+                let action = mcts.nodes[child].last_action.unwrap();
+                (action, mcts.visits[child], mcts.wins[child])
+            })
+            .collect();
+        statistics.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+        statistics
+    }
+
+    #[test]
+    fn advancing_the_root_keeps_the_subtree_statistics() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.get_best_move(2000).unwrap();
+        let child = mcts.nodes[mcts.root].children[0];
+        let action = mcts.nodes[child].last_action.unwrap();
+        let visits = (mcts.wins[child], mcts.visits[child]);
+        let below = child_statistics(&mcts, child);
+        assert!(!below.is_empty());
+
+        mcts.advance_root(action);
+        assert_eq!(mcts.wins_visits(mcts.root), visits);
+        assert_eq!(mcts.nodes[mcts.root].last_action, Some(action));
+        let mut statistics = mcts.move_statistics();
+        statistics.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+        assert_eq!(statistics, below);
+    }
+
+    #[test]
+    fn advancing_to_an_unexpanded_move_rebuilds_the_tree() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.advance_root(4);
+        assert_eq!(mcts.nodes.len(), 1);
+        assert_eq!(mcts.wins_visits(mcts.root), (0.0, 0.0));
+        assert_eq!(mcts.nodes[mcts.root].last_action, Some(4));
+        assert_eq!(
+            mcts.nodes[mcts.root].state.get_legal_moves(),
+            vec![0, 1, 2, 3, 5, 6, 7, 8]
+        );
+    }
+
+    #[test]
+    fn priors_bias_puct_towards_the_likely_move() {
+        let state = TicTacToe::new().favouring(8);
+        let mut mcts = MCTS::with_puct(Box::new(state), DEFAULT_C_PUCT);
+        mcts.rng = StdRng::seed_from_u64(0);
+        mcts.get_best_move(1).unwrap();
+        assert_eq!(mcts.move_statistics()[0].0, 8);
+        mcts.get_best_move(30).unwrap();
+        assert_eq!(mcts.move_statistics()[0].0, 8);
+
+        // With uniform priors the corner is just one of nine moves.
+        let mut mcts = MCTS::with_puct(Box::new(TicTacToe::new()), DEFAULT_C_PUCT);
+        mcts.rng = StdRng::seed_from_u64(0);
+        mcts.get_best_move(31).unwrap();
+        let statistics = mcts.move_statistics();
+        let corner = statistics.iter().find(|&&(action, _, _)| action == 8);
+        assert!(corner.map_or(0.0, |&(_, visits, _)| visits) < 10.0);
+    }
+
+    /// Plays `games` games of TicTacToe in which the engine, as X, searches
+    /// `iterations` iterations with rollouts by `policy`, against an O that
+    /// plays at random. Returns how many X won.
+    fn wins_against_random(
+        policy: impl Fn() -> Box<dyn RolloutPolicy<usize>>,
+        iterations: u32,
+        games: u64,
+    ) -> usize {
+        let mut wins = 0;
+        for game in 0..games {
+            let mut rng = StdRng::seed_from_u64(game);
+            let mut state = TicTacToe::new();
+            while !state.is_terminal() {
+                let action = if state.current_player == 0 {
+                    let mut mcts = MCTS::with_seed(state.box_clone(), game);
+                    mcts.set_rollout_policy(policy());
+                    mcts.get_best_move(iterations).unwrap()
+                } else {
+                    let moves = state.get_legal_moves();
+                    moves[rng.gen_range(0..moves.len())]
+                };
+                state.make_move(action);
+            }
+            if state.get_winner() == Some(GameResult::Win(0)) {
+                wins += 1;
+            }
+        }
+        wins
+    }
+
+    #[test]
+    fn greedy_rollouts_finish_games_and_win_more() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.set_rollout_policy(Box::new(GreedyRollout));
+        mcts.path = vec![0];
+        for _ in 0..20 {
+            let reward = mcts.simulate(0).unwrap().reward_for(0);
+            assert!([0.0, 0.5, 1.0].contains(&reward));
+        }
+
+        let random = wins_against_random(|| Box::new(RandomRollout), 10, 100);
+        let greedy = wins_against_random(|| Box::new(GreedyRollout), 10, 100);
+        assert!(greedy > random, "greedy {} random {}", greedy, random);
+    }
+
+    /// A game that never ends, whose positions are valued at a tenth of the
+    /// number of moves played to reach them.
+    #[derive(Clone)]
+    struct Endless {
+        plies: usize,
+        current_player: usize,
+    }
+
+    impl GameState for Endless {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            vec![0, 1]
+        }
+
+        fn make_move(&mut self, _action: usize) {
+            self.plies += 1;
+            self.current_player = 1 - self.current_player;
+        }
+
+        fn is_terminal(&self) -> bool {
+            false
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            None
+        }
+
+        fn current_player(&self) -> usize {
+            self.current_player
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        fn evaluate(&self) -> f64 {
+            self.plies as f64 / 10.0
+        }
+    }
+
+    #[test]
+    fn rollouts_stop_at_the_depth_limit() {
+        let start = Endless {
+            plies: 0,
+            current_player: 0,
+        };
+        let mut mcts = MCTS::with_seed(Box::new(start), 0);
+        mcts.set_max_rollout_depth(Some(7));
+        mcts.path = vec![0];
+        let reward = mcts.simulate(0).unwrap().reward_for(0);
+        assert!((reward - 0.7).abs() < 1e-12);
+
+        let late = Endless {
+            plies: 5,
+            current_player: 0,
+        };
+        let mut mcts = MCTS::with_seed(Box::new(late), 0);
+        mcts.set_max_rollout_depth(Some(7));
+        mcts.path = vec![0];
+        assert_eq!(mcts.simulate(0).unwrap().reward_for(0), 1.0);
+        assert!(mcts.get_best_move(50).is_ok());
+    }
+
+    #[test]
+    fn principal_variation_is_a_legal_line() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        assert!(mcts.principal_variation().is_empty());
+        let best = mcts.get_best_move(3000).unwrap();
+
+        let variation = mcts.principal_variation();
+        assert!(variation.len() >= 2);
+        assert_eq!(variation[0], best);
+        let mut state = TicTacToe::new();
+        for (ply, &action) in variation.iter().enumerate() {
+            assert!(state.get_legal_moves().contains(&action));
+            assert_eq!(state.current_player, ply % 2);
+            state.make_move(action);
+            assert_eq!(state.board[action], Some(ply % 2));
+        }
+    }
+
+    /// Players take turns claiming one of the free cells until all are
+    /// taken, and whoever holds cell `0` wins. Different move orders reach
+    /// the same positions, which `key` identifies when it is set.
+    #[derive(Clone)]
+    struct Placement {
+        cells: Vec<Option<usize>>,
+        current_player: usize,
+        key: Option<fn(&Placement) -> u64>,
+    }
+
+    impl Placement {
+        fn new(cells: usize, key: Option<fn(&Placement) -> u64>) -> Self {
+            Placement {
+                cells: vec![None; cells],
+                current_player: 0,
+                key,
+            }
+        }
+
+        /// A key that tells every position apart.
+        fn exact_key(&self) -> u64 {
+            self.cells.iter().fold(0, |key, cell| {
+                key * 3 + cell.map_or(0, |player| player as u64 + 1)
+            })
+        }
+
+        /// A key that only counts the claimed cells, so that every position
+        /// collides with all others at the same ply.
+        fn stone_count_key(&self) -> u64 {
+            self.cells.iter().filter(|cell| cell.is_some()).count() as u64
+        }
+    }
+
+    impl GameState for Placement {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            (0..self.cells.len())
+                .filter(|&cell| self.cells[cell].is_none())
+                .collect()
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.cells[action] = Some(self.current_player);
+            self.current_player = 1 - self.current_player;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.cells.iter().all(Option::is_some)
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            if self.is_terminal() {
+                self.cells[0].map(GameResult::Win)
+            } else {
+                None
+            }
+        }
+
+        fn current_player(&self) -> usize {
+            self.current_player
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        fn state_key(&self) -> Option<u64> {
+            self.key.map(|key| key(self))
+        }
+
+        fn serialize_state(&self) -> Option<Vec<u8>> {
+            let cells = self
+                .cells
+                .iter()
+                .map(|cell| cell.map_or(0, |player| player as u8 + 1));
+            Some(cells.chain([self.current_player as u8]).collect())
+        }
+    }
+
+    /// Whether some node of the tree is the child of more than one node.
+    fn has_shared_child<A: Copy + Eq>(mcts: &MCTS<A>) -> bool {
+        let mut parents = vec![0; mcts.nodes.len()];
+        for node in &mcts.nodes {
+            for &child in &node.children {
+                parents[child] += 1;
+            }
+        }
+        parents.into_iter().any(|count| count > 1)
+    }
+
+    #[test]
+    fn transpositions_share_nodes() {
+        let search = |key| {
+            let mut mcts = MCTS::with_seed(Box::new(Placement::new(6, key)), 0);
+            mcts.get_best_move(2000).unwrap();
+            mcts
+        };
+        let plain = search(None);
+        let shared = search(Some(Placement::exact_key));
+        assert!(!has_shared_child(&plain));
+        assert!(has_shared_child(&shared));
+        assert!(
+            shared.nodes.len() < plain.nodes.len(),
+            "{} nodes with transpositions, {} without",
+            shared.nodes.len(),
+            plain.nodes.len()
+        );
+    }
+
+    /// The share of the root visits that went to each root move, by move.
+    #[cfg(feature = "parallel")]
+    fn visit_shares(mcts: &MCTS<usize>) -> Vec<(usize, f64)> {
+        let mut statistics = mcts.move_statistics();
+        statistics.sort_by_key(|&(action, _, _)| action);
+        let total: f64 = statistics.iter().map(|&(_, visits, _)| visits).sum();
+        statistics
+            .into_iter()
+            .map(|(action, visits, _)| (action, visits / total))
+            .collect()
+    }
+
+    #[test]
+    fn colliding_keys_do_not_merge_different_positions() {
+        let mut mcts = MCTS::with_seed(
+            Box::new(Placement::new(6, Some(Placement::stone_count_key))),
+            0,
+        );
+        mcts.get_best_move(2000).unwrap();
+        for node in &mcts.nodes {
+            for &child in &node.children {
+                let action = mcts.nodes[child].last_action.unwrap();
+                assert_eq!(
+                    node.state.apply(action).serialize_state(),
+                    mcts.nodes[child].state.serialize_state()
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_rollouts_match_serial_statistics() {
+        const THREADS: usize = 4;
+        // Both searches average four rollouts per leaf.
+        let mut serial = MCTS::builder()
+            .seed(1)
+            .rollouts_per_leaf(THREADS)
+            .build(Box::new(TicTacToe::new()));
+        let serial_move = serial.get_best_move(2000).unwrap();
+        let mut parallel = MCTS::with_seed(Box::new(TicTacToe::new()), 1);
+        let parallel_move = parallel.get_best_move_parallel(2000, THREADS).unwrap();
+
+        assert_eq!(parallel_move, serial_move);
+        let distance: f64 = visit_shares(&serial)
+            .into_iter()
+            .zip(visit_shares(&parallel))
+            .map(|((_, serial), (_, parallel))| (serial - parallel).abs())
+            .sum::<f64>()
+            / 2.0;
+        assert!(distance < 0.1, "visit distributions differ by {}", distance);
+
+        // The rollout seeds come from the engine RNG.
+        let mut again = MCTS::with_seed(Box::new(TicTacToe::new()), 1);
+        again.get_best_move_parallel(2000, THREADS).unwrap();
+        assert_eq!(again.move_statistics(), parallel.move_statistics());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn root_parallel_agrees_with_serial_on_a_clear_position() {
+        // Whoever ends up holding cell 0 wins, so it should be taken at once.
+        let mut serial = MCTS::with_seed(Box::new(Placement::new(9, None)), 0);
+        let serial = serial.get_best_move(1000).unwrap();
+        let state_fn =
+            || -> Box<dyn GameState<Action = usize>> { Box::new(Placement::new(9, None)) };
+        let parallel = MCTS::get_best_move_root_parallel(state_fn, 1000, 4).unwrap();
+        assert_eq!(parallel, 0);
+        assert_eq!(parallel, serial);
+    }
+
+    #[test]
+    fn virtual_loss_spreads_concurrent_descents() {
+        let children = [(0, 5.0, 10.0), (1, 5.0, 10.0), (2, 5.0, 10.0)];
+
+        let mut mcts = hand_built(&children);
+        mcts.set_virtual_loss(1.0);
+        let first = mcts.select(0);
+        let second = mcts.select(0);
+        assert_ne!(first, second);
+        // The virtual loss stays until the iterations are backpropagated.
+        assert_eq!(mcts.visits[first], 11.0);
+        mcts.revert_virtual_loss();
+        assert_eq!(mcts.visits[second], 10.0);
+
+        let mut mcts = hand_built(&children);
+        let first = mcts.select(0);
+        let second = mcts.select(0);
+        assert_eq!(first, second);
+        assert_eq!(mcts.visits[first], 10.0);
+    }
+
+    #[test]
+    fn tree_size_and_depth_grow_with_the_search() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        assert_eq!(mcts.node_count(), 1);
+        assert_eq!(mcts.tree_depth(), 0);
+
+        mcts.get_best_move(300).unwrap();
+        assert!(mcts.node_count() > 1);
+        assert!(mcts.tree_depth() > 1);
+        assert!(mcts.tree_depth() <= mcts.node_count());
+        assert!(mcts.tree_depth() <= 9);
+    }
+
+    /// Three players each pick `0` or `1` in turn, and player `sum % 3`
+    /// wins.
+    #[derive(Clone)]
+    struct ThreePicks {
+        picks: Vec<usize>,
+    }
+
+    impl GameState for ThreePicks {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.is_terminal() {
+                Vec::new()
+            } else {
+                vec![0, 1]
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.picks.push(action);
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.picks.len() == 3
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.is_terminal()
+                .then(|| GameResult::Win(self.picks.iter().sum::<usize>() % 3))
+        }
+
+        fn current_player(&self) -> usize {
+            self.picks.len() % 3
+        }
+
+        fn num_players(&self) -> usize {
+            3
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn each_of_three_players_is_credited_with_their_own_wins() {
+        let mut mcts = MCTS::with_seed(Box::new(ThreePicks { picks: Vec::new() }), 0);
+        mcts.run_iterations(500).unwrap();
+        assert_eq!(mcts.node_count(), 15);
+        for (index, node) in mcts.nodes.iter().enumerate() {
+            let (wins, visits) = mcts.wins_visits(index);
+            assert!(visits > 0.0);
+            match node.state.get_winner() {
+                Some(GameResult::Win(winner)) if winner == node.player_just_moved => {
+                    assert_eq!(wins, visits)
+                }
+                Some(_) => assert_eq!(wins, 0.0),
+                None => assert!((0.0..=visits).contains(&wins)),
+            }
+        }
+
+        // A single iteration won by player 2 only credits the nodes player
+        // 2 moved into.
+        let mut mcts = MCTS::with_seed(Box::new(ThreePicks { picks: Vec::new() }), 0);
+        let mut state = ThreePicks { picks: Vec::new() };
+        mcts.path = vec![0];
+        for (index, action) in [1, 1, 0].into_iter().enumerate() {
+            mcts.nodes[index].untried_actions = vec![action];
+            mcts.expand(index);
+            state.make_move(action);
+        }
+        let outcome = mcts.terminal_outcome(&state, state.get_winner()).unwrap();
+        mcts.backpropagate(outcome);
+        assert_eq!(mcts.wins, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(mcts.nodes[3].player_just_moved, 2);
+    }
+
+    #[test]
+    fn root_noise_changes_root_selection_only() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.add_root_noise(0.3, 0.5);
+        assert!(mcts.nodes.iter().all(|node| node.noise.is_none()));
+
+        let legal = TicTacToe::new().get_legal_moves();
+        let mut changed = 0;
+        for seed in 0..10 {
+            let search = || {
+                let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), seed);
+                mcts.get_best_move(200).unwrap();
+                mcts
+            };
+            let mut mcts = search();
+            let mut noisy = search();
+            noisy.add_root_noise(0.3, 0.5);
+            let root_children = noisy.nodes[0].children.clone();
+            for (index, node) in noisy.nodes.iter().enumerate() {
+                assert_eq!(node.noise.is_some(), root_children.contains(&index));
+            }
+
+            // The first step of the descent is the root child selected.
+            mcts.select(0);
+            noisy.select(0);
+            let plain_move = mcts.nodes[mcts.path[1]].last_action.unwrap();
+            let noisy_move = noisy.nodes[noisy.path[1]].last_action.unwrap();
+            assert!(legal.contains(&noisy_move));
+            if noisy_move != plain_move {
+                changed += 1;
+            }
+        }
+        assert!(changed > 0);
+    }
+
+    #[test]
+    fn temperature_spreads_sampled_moves() {
+        let mut mcts = hand_built(&[(0, 5.0, 20.0), (1, 5.0, 10.0), (2, 2.0, 5.0)]);
+        mcts.rng = StdRng::seed_from_u64(0);
+        let mut counts = [0; 3];
+        for _ in 0..300 {
+            counts[mcts.sample_move(100.0).unwrap()] += 1;
+        }
+        assert!(counts.iter().all(|&count| count > 50), "{:?}", counts);
+
+        for _ in 0..50 {
+            assert_eq!(mcts.sample_move(0.0), Ok(0));
+        }
+        // A low temperature all but always picks the most visited move.
+        let low = (0..50).filter(|_| mcts.sample_move(0.05) == Ok(0)).count();
+        assert_eq!(low, 50);
+    }
+
+    /// A path in the temporary directory that no other test uses.
+    #[cfg(feature = "std")]
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("mcts_rs_{}_{}", std::process::id(), name))
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn saved_tree_loads_with_the_same_statistics() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::from_board("X...O....")), 0);
+        mcts.get_best_move(1000).unwrap();
+        let path = temp_path("round_trip.tree");
+        mcts.save(&path).unwrap();
+        let loaded = MCTS::load(&path, TicTacToe::deserialize);
+        fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.move_statistics(), mcts.move_statistics());
+        assert_eq!(loaded.node_count(), mcts.node_count());
+        assert_eq!(loaded.principal_variation(), mcts.principal_variation());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn saving_needs_serializable_states() {
+        let mcts = MCTS::with_seed(
+            Box::new(Corridor {
+                remaining: 3,
+                current_player: 0,
+            }),
+            0,
+        );
+        let path = temp_path("unsupported.tree");
+        let error = mcts.save(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+        assert!(!path.exists());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn loading_checks_the_header() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.get_best_move(50).unwrap();
+        let path = temp_path("header.tree");
+        mcts.save(&path).unwrap();
+        let saved = fs::read(&path).unwrap();
+
+        let mut other_format = saved.clone();
+        other_format[..4].copy_from_slice(b"TREE");
+        let mut other_version = saved;
+        other_version[4..12].copy_from_slice(&(SAVE_VERSION + 1).to_le_bytes());
+        for bytes in [other_format, other_version] {
+            fs::write(&path, bytes).unwrap();
+            let error = MCTS::load(&path, TicTacToe::deserialize).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn dot_output_declares_each_node_once() {
+        let mcts = hand_built(&[(0, 1.0, 2.0), (4, 3.0, 5.0), (8, 0.5, 1.0)]);
+        let count = |dot: &str, edges: bool| {
+            dot.lines()
+                .filter(|line| line.contains("[label=") && line.contains("->") == edges)
+                .count()
+        };
+
+        let dot = mcts.to_dot(1);
+        assert!(dot.starts_with("digraph MCTS {") && dot.ends_with("}\n"));
+        assert_eq!(count(&dot, false), 4);
+        assert_eq!(count(&dot, true), 3);
+        assert!(dot.contains("n0 -> n2 [label=\"4\"];"));
+        assert!(dot.contains("n2 [label=\"3.0/5\"];"));
+
+        let dot = mcts.to_dot(0);
+        assert_eq!(count(&dot, false), 1);
+        assert_eq!(count(&dot, true), 0);
+    }
+
+    #[test]
+    fn ucb1_tuned_explores_low_variance_arms_less() {
+        // A clearly best arm against a weaker one that has been tried far
+        // less. Both pay out 0 or 1, so their squared rewards sum to their
+        // wins.
+        let mut mcts = hand_built(&[(0, 54.0, 60.0), (1, 6.0, 10.0)]);
+        for child in [1, 2] {
+            mcts.nodes[child].sum_sq = mcts.wins[child];
+        }
+
+        // UCB1 still spends the next iteration on the weaker arm, while
+        // UCB1-Tuned sees that neither arm is noisy enough to need it.
+        assert_eq!(mcts.select(0), 2);
+        mcts.set_selection_policy(SelectionPolicy::Ucb1Tuned);
+        assert_eq!(mcts.select(0), 1);
+    }
+
+    #[test]
+    fn progressive_widening_grows_children_sublinearly() {
+        let mut mcts = MCTS::with_seed(Box::new(Wide::new(200, 20)), 5);
+        mcts.set_progressive_widening(1.0, 0.5);
+        let mut samples = Vec::new();
+        for iterations in [100, 300, 1200] {
+            mcts.get_best_move(iterations).unwrap();
+            let visits = mcts.visits[0];
+            let children = mcts.nodes[0].children.len() as f64;
+            assert!(children <= (visits + 1.0).sqrt().floor());
+            samples.push((visits, children));
+        }
+        let (first_visits, first_children) = samples[0];
+        let (last_visits, last_children) = samples[2];
+        assert_eq!(last_visits, 1600.0);
+        assert!(last_children > first_children);
+        assert!(last_children / first_children < 0.5 * last_visits / first_visits);
+    }
+
+    /// Players take turns claiming cells worth `1..=size` points until none
+    /// are left, and the larger total wins. Only the set of cells each player
+    /// claimed matters, not the order. With `ordered` set the most valuable
+    /// cells are expanded first.
+    #[derive(Clone)]
+    struct Claim {
+        owners: Vec<Option<usize>>,
+        current_player: usize,
+        ordered: bool,
+    }
+
+    impl Claim {
+        fn new(size: usize) -> Self {
+            Claim {
+                owners: vec![None; size],
+                current_player: 0,
+                ordered: false,
+            }
+        }
+
+        fn ordered(mut self) -> Self {
+            self.ordered = true;
+            self
+        }
+
+        fn score(&self, player: usize) -> usize {
+            self.owners
+                .iter()
+                .enumerate()
+                .filter(|&(_, &owner)| owner == Some(player))
+                .map(|(cell, _)| cell + 1)
+                .sum()
+        }
+    }
+
+    impl GameState for Claim {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            (0..self.owners.len())
+                .filter(|&cell| self.owners[cell].is_none())
+                .collect()
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.owners[action] = Some(self.current_player);
+            self.current_player = 1 - self.current_player;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.owners.iter().all(Option::is_some)
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            if !self.is_terminal() {
+                return None;
+            }
+            Some(match self.score(0).cmp(&self.score(1)) {
+                std::cmp::Ordering::Greater => GameResult::Win(0),
+                std::cmp::Ordering::Less => GameResult::Win(1),
+                std::cmp::Ordering::Equal => GameResult::Draw,
+            })
+        }
+
+        fn current_player(&self) -> usize {
+            self.current_player
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        // Cells are worth their index plus one, so ascending order puts the
+        // most valuable last.
+        fn order_moves(&self, moves: &mut Vec<usize>) -> bool {
+            if self.ordered {
+                moves.sort_unstable();
+            }
+            self.ordered
+        }
+    }
+
+    /// The points of the cells chosen first in `Claim`, summed over a few
+    /// seeded searches of `iterations` iterations.
+    fn claimed_points(rave: Option<f64>, iterations: u32) -> usize {
+        (0..20)
+            .map(|seed| {
+                let state = Box::new(Claim::new(16));
+                let mut mcts = match rave {
+                    Some(equivalence) => MCTS::with_rave(state, equivalence),
+                    None => MCTS::new(state),
+                };
+                mcts.rng = StdRng::seed_from_u64(seed);
+                mcts.get_best_move(iterations).unwrap() + 1
+            })
+            .sum()
+    }
+
+    #[test]
+    fn rave_finds_valuable_cells_with_fewer_iterations() {
+        let plain = claimed_points(None, 150);
+        let rave = claimed_points(Some(300.0), 150);
+        assert!(rave > plain, "RAVE {} plain {}", rave, plain);
+    }
+
+    #[test]
+    fn first_expansions_do_not_follow_the_move_order() {
+        let expansions = |seed| {
+            let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), seed);
+            mcts.get_best_move(5).unwrap();
+            mcts.nodes[0]
+                .children
+                .iter()
+                .map(|&child| mcts.nodes[child].last_action.unwrap())
+                .collect::<Vec<usize>>()
+        };
+        let first = expansions(9);
+        assert_eq!(first, expansions(9));
+        assert_ne!(first, vec![8, 7, 6, 5, 4]);
+        assert_ne!(first, vec![0, 1, 2, 3, 4]);
+        assert_ne!(first, expansions(10));
+    }
+
+    #[test]
+    fn reset_keeps_the_node_capacity() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.get_best_move(200).unwrap();
+        let capacity = mcts.nodes.capacity();
+        mcts.reset(Box::new(TicTacToe::from_board("X...O....")));
+        assert_eq!(mcts.node_count(), 1);
+        assert_eq!(mcts.nodes.capacity(), capacity);
+        assert_eq!(mcts.visits[0], 0.0);
+        assert!(mcts.get_best_move(100).is_ok());
+    }
+
+    #[test]
+    fn capped_tree_never_exceeds_max_nodes() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.set_max_nodes(Some(30));
+        for _ in 0..50 {
+            assert!(mcts.get_best_move(20).is_ok());
+            assert!(mcts.node_count() <= 30);
+        }
+        assert_eq!(mcts.node_count(), 30);
+        assert_eq!(mcts.visits[0], 1000.0);
+    }
+
+    #[test]
+    fn breaking_callback_stops_the_search() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        let mut calls = 0;
+        mcts.get_best_move_with_callback(1000, |iteration, _| {
+            calls += 1;
+            if iteration == 9 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+        assert_eq!(calls, 10);
+        assert_eq!(mcts.visits[0], 10.0);
+        // One node per iteration, plus the root.
+        assert!(mcts.node_count() <= 11);
+    }
+
+    /// Player `0` ends the game on the first move by winning with a margin
+    /// of `0..=4` points.
+    #[derive(Clone)]
+    struct Margin {
+        margin: Option<usize>,
+    }
+
+    impl GameState for Margin {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            match self.margin {
+                None => (0..=4).collect(),
+                Some(_) => Vec::new(),
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.margin = Some(action);
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.margin.is_some()
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.margin.map(|_| GameResult::Win(0))
+        }
+
+        fn current_player(&self) -> usize {
+            if self.margin.is_none() {
+                0
+            } else {
+                1
+            }
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        fn terminal_reward(&self, _result: GameResult, player: usize) -> f64 {
+            let reward = 0.5 + self.margin.unwrap() as f64 / 8.0;
+            if player == 0 {
+                reward
+            } else {
+                1.0 - reward
+            }
+        }
+    }
+
+    #[test]
+    fn wider_margins_back_up_higher_values() {
+        let mut mcts = MCTS::with_seed(Box::new(Margin { margin: None }), 0);
+        let best = mcts.get_best_move_with(200, FinalMoveSelection::HighestValue);
+        assert_eq!(best, Ok(4));
+        let mut values: Vec<(usize, f64)> = child_statistics(&mcts, mcts.root)
+            .into_iter()
+            .map(|(margin, visits, wins)| (margin, wins / visits))
+            .collect();
+        assert_eq!(values.len(), 5);
+        values.sort_by_key(|&(margin, _)| margin);
+        for (margin, value) in values {
+            assert!((value - (0.5 + margin as f64 / 8.0)).abs() < 1e-9);
+        }
+    }
+
+    /// Player `0` picks a short (`0`) or a long (`1`) line, and wins once the
+    /// forced moves along it have been played out.
+    #[derive(Clone)]
+    struct TwoLines {
+        remaining: Option<usize>,
+        plies: usize,
+    }
+
+    impl GameState for TwoLines {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            match self.remaining {
+                None => vec![0, 1],
+                Some(0) => Vec::new(),
+                Some(_) => vec![0],
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.remaining = match self.remaining {
+                None if action == 0 => Some(1),
+                None => Some(5),
+                Some(remaining) => Some(remaining - 1),
+            };
+            self.plies += 1;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.remaining == Some(0)
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.is_terminal().then_some(GameResult::Win(0))
+        }
+
+        fn current_player(&self) -> usize {
+            self.plies % 2
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn discounting_prefers_the_shorter_win() {
+        let start = TwoLines {
+            remaining: None,
+            plies: 0,
+        };
+        let line_values = |mcts: &MCTS<usize>| {
+            let mut values: Vec<(usize, f64)> = child_statistics(mcts, mcts.root)
+                .into_iter()
+                .map(|(line, visits, wins)| (line, wins / visits))
+                .collect();
+            values.sort_by_key(|&(line, _)| line);
+            values
+        };
+
+        let mut undiscounted = MCTS::with_seed(start.box_clone(), 0);
+        undiscounted.get_best_move(200).unwrap();
+        assert_eq!(line_values(&undiscounted), vec![(0, 1.0), (1, 1.0)]);
+
+        let mut discounted = MCTS::with_seed(start.box_clone(), 0);
+        discounted.set_discount(0.9);
+        assert_eq!(discounted.get_best_move(200).unwrap(), 0);
+        let values = line_values(&discounted);
+        assert!(values[0].1 > values[1].1);
+    }
+
+    #[test]
+    fn best_child_value_reads_the_most_visited_move() {
+        let mcts = hand_built(&[(0, 1.0, 2.0), (4, 3.0, 5.0), (8, 0.5, 1.0)]);
+        assert_eq!(mcts.principal_variation()[0], 4);
+        assert_eq!(mcts.best_child_value(), Some(0.6));
+        assert!(MCTS::new(Box::new(TicTacToe::new()))
+            .best_child_value()
+            .is_none());
+    }
+
+    #[test]
+    fn best_child_value_estimates_the_outcome() {
+        let value = |cells| {
+            let mut mcts = MCTS::with_seed(Box::new(TicTacToe::from_board(cells)), 0);
+            mcts.run_iterations(2000).unwrap();
+            mcts.best_child_value().unwrap()
+        };
+        // X completes the top row.
+        assert!(value("XX.OO....") > 0.9);
+        // O threatens both the top row and the left column.
+        assert!(value("OO.O.X.X.") < 0.1);
+    }
+
+    #[test]
+    fn single_move_is_returned_without_searching() {
+        let corridor = Corridor {
+            remaining: 3,
+            current_player: 0,
+        };
+        let mut mcts = MCTS::with_seed(Box::new(corridor), 0);
+        assert_eq!(mcts.get_best_move(1000).unwrap(), 0);
+        assert_eq!(mcts.node_count(), 1);
+        assert_eq!(mcts.num_simulations(), 0);
+        #[cfg(feature = "std")]
+        assert_eq!(mcts.get_best_move_timed(Duration::from_secs(5)).unwrap(), 0);
+        assert_eq!(mcts.num_simulations(), 0);
+
+        let finished = Corridor {
+            remaining: 0,
+            current_player: 0,
+        };
+        assert!(MCTS::new(Box::new(finished)).get_best_move(10).is_err());
+    }
+
+    /// A broken game whose moves are renumbered every time they are listed.
+    #[derive(Clone)]
+    struct ShiftingMoves {
+        listings: std::cell::Cell<usize>,
+        plies: usize,
+    }
+
+    impl GameState for ShiftingMoves {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.is_terminal() {
+                return Vec::new();
+            }
+            let listing = self.listings.get();
+            self.listings.set(listing + 1);
+            (3 * listing..3 * listing + 3).collect()
+        }
+
+        fn make_move(&mut self, _action: usize) {
+            self.plies += 1;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.plies == 4
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.is_terminal().then_some(GameResult::Draw)
+        }
+
+        fn current_player(&self) -> usize {
+            0
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "get_legal_moves no longer reports")]
+    fn expanding_an_illegal_action_panics() {
+        let state = ShiftingMoves {
+            listings: std::cell::Cell::new(0),
+            plies: 0,
+        };
+        let mut mcts = MCTS::with_seed(Box::new(state), 0);
+        let _ = mcts.get_best_move(10);
+    }
+
+    /// Picks one past the last legal move.
+    struct OffByOne;
+
+    impl RolloutPolicy<usize> for OffByOne {
+        fn choose(
+            &mut self,
+            _state: &dyn GameState<Action = usize>,
+            moves: &[usize],
+            _rng: &mut dyn RngCore,
+        ) -> usize {
+            moves[moves.len() - 1] + 1
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "outside get_legal_moves")]
+    fn illegal_rollout_moves_panic() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.set_rollout_policy(Box::new(OffByOne));
+        let _ = mcts.get_best_move(10);
+    }
+
+    /// Plays random moves and counts how many it was asked for.
+    struct CountingRollout(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl RolloutPolicy<usize> for CountingRollout {
+        fn choose(
+            &mut self,
+            _state: &dyn GameState<Action = usize>,
+            moves: &[usize],
+            rng: &mut dyn RngCore,
+        ) -> usize {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            moves[rng.gen_range(0..moves.len())]
+        }
+    }
+
+    #[test]
+    fn builder_settings_take_effect() {
+        let top_share = |exploration| {
+            let mut mcts = MCTS::builder()
+                .seed(1)
+                .exploration(exploration)
+                .build(Box::new(TicTacToe::new()));
+            mcts.get_best_move(500).unwrap();
+            child_statistics(&mcts, mcts.root)[0].1 / mcts.visits[mcts.root]
+        };
+        assert!(top_share(0.1) > top_share(10.0));
+
+        let statistics = |seed| {
+            let mut mcts = MCTS::builder().seed(seed).build(Box::new(TicTacToe::new()));
+            mcts.get_best_move(100).unwrap();
+            mcts.move_statistics()
+        };
+        assert_eq!(statistics(3), statistics(3));
+
+        let moves = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .max_nodes(10)
+            .rollout_policy(Box::new(CountingRollout(moves.clone())))
+            .build(Box::new(TicTacToe::new()));
+        mcts.get_best_move(100).unwrap();
+        assert_eq!(mcts.node_count(), 10);
+        assert!(moves.load(std::sync::atomic::Ordering::Relaxed) > 100);
+    }
+
+    #[test]
+    fn accessors_rebuild_the_move_statistics() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 4);
+        let best = mcts.get_best_move(300).unwrap();
+        let mut rebuilt: Vec<(usize, f64, f64)> = mcts
+            .children_of(mcts.root())
+            .iter()
+            .map(|&child| {
+                let (wins, visits) = mcts.wins_visits(child);
+                (mcts.last_action_of(child).unwrap(), visits, wins)
+            })
+            .collect();
+        let mut statistics = mcts.move_statistics();
+        rebuilt.sort_by_key(|&(action, _, _)| action);
+        statistics.sort_by_key(|&(action, _, _)| action);
+        assert_eq!(rebuilt, statistics);
+        assert_eq!(mcts.last_action_of(mcts.root()), None);
+        assert_eq!(
+            mcts.last_action_of(mcts.most_visited_child_index().unwrap()),
+            Some(best)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn accessors_reject_indices_outside_the_tree() {
+        let mcts = MCTS::new(Box::new(TicTacToe::new()));
+        mcts.wins_visits(1);
+    }
+
+    /// Player `0` guesses a hidden coin, winning on a right guess and losing
+    /// on a wrong one, or folds (`2`) for a sure reward of `0.7`.
+    #[derive(Clone)]
+    struct HiddenCoin {
+        coin: usize,
+        guess: Option<usize>,
+    }
+
+    impl GameState for HiddenCoin {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            match self.guess {
+                None => vec![0, 1, 2],
+                Some(_) => Vec::new(),
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.guess = Some(action);
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.guess.is_some()
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.guess.map(|guess| {
+                if guess == 2 || guess == self.coin {
+                    GameResult::Win(0)
+                } else {
+                    GameResult::Win(1)
+                }
+            })
+        }
+
+        fn current_player(&self) -> usize {
+            if self.guess.is_none() {
+                0
+            } else {
+                1
+            }
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        fn terminal_reward(&self, _result: GameResult, player: usize) -> f64 {
+            let reward = match self.guess {
+                Some(2) => 0.7,
+                Some(guess) if guess == self.coin => 1.0,
+                _ => 0.0,
+            };
+            if player == 0 {
+                reward
+            } else {
+                1.0 - reward
+            }
+        }
+
+        fn determinize(&self, rng: &mut dyn RngCore) -> Box<dyn GameState<Action = usize>> {
+            Box::new(HiddenCoin {
+                coin: rng.gen_range(0..2),
+                guess: self.guess,
+            })
+        }
+    }
+
+    #[test]
+    fn determinized_search_does_not_peek_at_hidden_state() {
+        let coin = HiddenCoin {
+            coin: 1,
+            guess: None,
+        };
+        // Searching the true state sees the coin.
+        let mut peeking = MCTS::with_seed(coin.box_clone(), 0);
+        assert_eq!(
+            peeking
+                .get_best_move_with(300, FinalMoveSelection::HighestValue)
+                .unwrap(),
+            1
+        );
+
+        for seed in 0..5 {
+            let mut mcts = MCTS::with_seed(coin.box_clone(), seed);
+            assert_eq!(mcts.get_best_move_determinized(1000).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn unvisited_children_are_selected_first() {
+        let children = [(0, 9.0, 10.0), (4, 0.0, 0.0), (8, 10.0, 10.0)];
+        for policy in [
+            SelectionPolicy::Ucb1,
+            SelectionPolicy::Ucb1Tuned,
+            SelectionPolicy::Puct,
+        ] {
+            let mut mcts = hand_built(&children);
+            // Rewards of 0 or 1 square to themselves.
+            for child in 1..=3 {
+                mcts.nodes[child].sum_sq = mcts.wins[child];
+            }
+            mcts.set_selection_policy(policy);
+            let selected = mcts.select(0);
+            assert_eq!(mcts.nodes[selected].last_action, Some(4), "{:?}", policy);
+        }
+        let mut mcts = hand_built(&children);
+        mcts.get_best_move(20).unwrap();
+        assert!(mcts.wins_visits(2).1 > 0.0);
+    }
+
+    #[test]
+    fn equal_visits_are_broken_by_value_then_creation_order() {
+        let mcts = hand_built(&[(5, 6.0, 10.0), (2, 7.0, 10.0), (8, 3.0, 4.0)]);
+        assert_eq!(mcts.principal_variation()[0], 2);
+
+        let mcts = hand_built(&[(5, 6.0, 10.0), (2, 6.0, 10.0), (8, 3.0, 4.0)]);
+        assert_eq!(mcts.principal_variation()[0], 5);
+
+        // Equal selection scores go to the child created first too.
+        let mut mcts = hand_built(&[(5, 6.0, 10.0), (2, 6.0, 10.0)]);
+        let selected = mcts.select(0);
+        assert_eq!(mcts.nodes[selected].last_action, Some(5));
+    }
+
+    #[test]
+    fn stop_flag_set_from_another_thread_ends_the_search() {
+        // A game that never ends only stops searching when told to.
+        let start = Endless {
+            plies: 0,
+            current_player: 0,
+        };
+        let stop = AtomicBool::new(false);
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .max_rollout_depth(10)
+            .build(Box::new(start));
+        let action = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(50));
+                stop.store(true, Ordering::Relaxed);
+            });
+            mcts.get_best_move_until(&stop).unwrap()
+        });
+        assert!(action < 2);
+        assert!(mcts.visits[mcts.root] >= f64::from(TIME_CHECK_INTERVAL));
+    }
+
+    #[test]
+    fn progressive_bias_explores_the_favoured_move_early() {
+        let favoured_visits = |weight| {
+            let mut mcts = MCTS::builder()
+                .seed(2)
+                .progressive_bias(weight)
+                .build(Box::new(TicTacToe::new().favouring(1)));
+            mcts.get_best_move(40).unwrap();
+            child_statistics(&mcts, mcts.root)
+                .into_iter()
+                .find(|&(action, _, _)| action == 1)
+                .map_or(0.0, |(_, visits, _)| visits)
+        };
+        let unbiased = favoured_visits(0.0);
+        let biased = favoured_visits(5.0);
+        assert!(
+            biased > 2.0 * unbiased,
+            "biased {} unbiased {}",
+            biased,
+            unbiased
+        );
+    }
+
+    #[test]
+    fn derived_clone_and_box_clone_coexist() {
+        let mut game = TicTacToe::new();
+        let copy = game.clone();
+        let boxed: Box<dyn GameState<Action = usize>> = game.box_clone();
+        let boxed_copy = boxed.clone();
+        game.make_move(4);
+        assert_eq!(copy.get_legal_moves().len(), 9);
+        assert_eq!(boxed.get_legal_moves().len(), 9);
+        assert_eq!(boxed_copy.get_legal_moves().len(), 9);
+        assert_eq!(game.get_legal_moves().len(), 8);
+    }
+
+    #[test]
+    fn flat_monte_carlo_blocks_immediate_losses() {
+        for seed in 0..5 {
+            for (cells, block) in [("OO..X...X", 2), ("XO..O...X", 7)] {
+                let mut mcts = MCTS::with_seed(Box::new(TicTacToe::from_board(cells)), seed);
+                assert_eq!(mcts.get_best_move_flat(200).unwrap(), block, "{}", cells);
+                assert_eq!(mcts.node_count(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn report_counts_the_iterations_that_ran() {
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .solver(true)
+            .build(Box::new(TicTacToe::from_board("XX.OO....")));
+        let (action, report) = mcts.get_best_move_report(1000).unwrap();
+        assert_eq!(action, 2);
+        assert!(report.iterations < 1000);
+        assert_eq!(
+            f64::from(report.iterations),
+            mcts.wins_visits(mcts.root()).1
+        );
+        assert_eq!(report.nodes_created, mcts.node_count() - 1);
+        assert!(report.max_depth >= 1);
+    }
+
+    #[test]
+    fn engine_searches_on_another_thread() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        let handle = std::thread::spawn(move || {
+            let action = mcts.get_best_move(500).unwrap();
+            (action, mcts)
+        });
+        let (action, mcts) = handle.join().unwrap();
+        assert!(TicTacToe::new().get_legal_moves().contains(&action));
+        assert_eq!(mcts.principal_variation()[0], action);
+    }
+
+    #[test]
+    fn decisive_rollouts_win_more_than_random_ones() {
+        let random = wins_against_random(|| Box::new(RandomRollout), 10, 100);
+        let decisive = wins_against_random(|| Box::new(DecisiveRollout), 10, 100);
+        assert!(decisive > random, "decisive {} random {}", decisive, random);
+    }
+
+    /// TicTacToe counting the calls to its `get_winner`.
+    #[derive(Clone)]
+    struct CountedWinner {
+        game: TicTacToe,
+        calls: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl GameState for CountedWinner {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            self.game.get_legal_moves()
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.game.make_move(action);
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.game.is_terminal()
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.calls
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            self.game.get_winner()
+        }
+
+        fn current_player(&self) -> usize {
+            self.game.current_player()
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn winners_are_computed_once_per_node() {
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let state = CountedWinner {
+            game: TicTacToe::from_board("XO.XO...."),
+            calls: calls.clone(),
+        };
+        // Rollouts that stop at once leave the nodes as the only callers.
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .max_rollout_depth(0)
+            .build(Box::new(state));
+        mcts.get_best_move(2000).unwrap();
+        let calls = calls.load(std::sync::atomic::Ordering::Relaxed);
+        assert!(calls > 0);
+        assert!(calls <= mcts.node_count(), "{} calls", calls);
+    }
+
+    #[test]
+    fn expand_all_creates_every_child_at_once() {
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .expand_all(true)
+            .build(Box::new(TicTacToe::new()));
+        mcts.get_best_move(1).unwrap();
+        assert_eq!(mcts.children_of(mcts.root).len(), 9);
+        assert!(mcts.nodes[mcts.root].untried_actions.is_empty());
+        // Only the child that was simulated has been visited.
+        let visited = mcts
+            .children_of(mcts.root)
+            .iter()
+            .filter(|&&child| mcts.wins_visits(child).1 > 0.0)
+            .count();
+        assert_eq!(visited, 1);
+    }
+
+    /// A broken game that runs out of moves after two plies without ever
+    /// ending.
+    #[derive(Clone)]
+    struct Stalemate {
+        plies: usize,
+    }
+
+    impl GameState for Stalemate {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.plies < 2 {
+                vec![0, 1]
+            } else {
+                Vec::new()
+            }
+        }
+
+        fn make_move(&mut self, _action: usize) {
+            self.plies += 1;
+        }
+
+        fn is_terminal(&self) -> bool {
+            false
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            None
+        }
+
+        fn current_player(&self) -> usize {
+            self.plies % 2
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn running_out_of_moves_scores_a_draw() {
+        let mut mcts = MCTS::with_seed(Box::new(Stalemate { plies: 0 }), 0);
+        assert!(mcts.get_best_move(100).is_ok());
+        for &child in mcts.children_of(mcts.root) {
+            let (wins, visits) = mcts.wins_visits(child);
+            assert_eq!(wins, 0.5 * visits);
+        }
+
+        let mut mcts = MCTS::with_seed(Box::new(Stalemate { plies: 2 }), 0);
+        mcts.path = vec![0];
+        assert_eq!(mcts.simulate(0).unwrap().reward_for(0), 0.5);
+
+        #[cfg(feature = "parallel")]
+        {
+            let mut mcts = MCTS::with_seed(Box::new(Stalemate { plies: 0 }), 0);
+            assert!(mcts.get_best_move_parallel(100, 2).is_ok());
+        }
+    }
+
+    #[test]
+    fn locked_decision_stops_the_search_early() {
+        let search = |early_stop| {
+            // Capping the tree at the root's children keeps every iteration
+            // a rollout from one of them, so the search runs until it is
+            // stopped.
+            let mut mcts = MCTS::builder()
+                .seed(0)
+                .max_nodes(6)
+                .early_stop(early_stop)
+                .build(Box::new(TicTacToe::from_board("XX.OO....")));
+            let (action, report) = mcts.get_best_move_report(20_000).unwrap();
+            assert_eq!(action, 2);
+            report.iterations
+        };
+        assert_eq!(search(false), 20_000);
+        assert!(search(true) < 15_000);
+    }
+
+    #[test]
+    fn game_results_map_to_rewards() {
+        for result in [GameResult::Win(0), GameResult::Win(1), GameResult::Draw] {
+            let expected = match result {
+                GameResult::Win(0) => [1.0, 0.0],
+                GameResult::Win(_) => [0.0, 1.0],
+                GameResult::Draw => [0.5, 0.5],
+            };
+            assert_eq!([result.reward_for(0), result.reward_for(1)], expected);
+        }
+        assert_eq!(GameResult::Win(2).reward_for(2), 1.0);
+        assert_eq!(GameResult::Win(2).reward_for(0), 0.0);
+    }
+
+    #[test]
+    fn reserved_capacity_is_not_reallocated() {
+        let mut mcts = MCTS::with_capacity(Box::new(TicTacToe::new()), 4096);
+        let capacity = mcts.node_capacity();
+        assert!(capacity >= 4096);
+        mcts.get_best_move(1000).unwrap();
+        assert!(mcts.node_count() < 4096);
+        assert_eq!(mcts.node_capacity(), capacity);
+    }
+
+    /// A node with its statistics stored inline, next to the cold data, as
+    /// they were before `wins` and `visits` moved into arrays of their own.
+    struct InlineNode {
+        _state: Box<dyn GameState<Action = usize>>,
+        children: Vec<NodeIndex>,
+        wins: f64,
+        visits: f64,
+    }
+
+    /// Compares scanning children for the best UCB1 score through the
+    /// engine's separate statistics arrays and through inline statistics.
+    /// Run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn benchmark_statistics_layouts_on_a_wide_tree() {
+        let mut mcts = MCTS::with_seed(Box::new(Wide::new(300, 3)), 0);
+        mcts.get_best_move(200_000).unwrap();
+        let inline: Vec<InlineNode> = mcts
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| InlineNode {
+                _state: node.state.box_clone(),
+                children: node.children.clone(),
+                wins: mcts.wins[index],
+                visits: mcts.visits[index],
+            })
+            .collect();
+        let parents: Vec<NodeIndex> = (0..mcts.nodes.len())
+            .filter(|&index| mcts.nodes[index].children.len() > 1)
+            .collect();
+        let score = |wins: f64, visits: f64, log_parent_visits: f64| {
+            wins / visits + (2.0 * log_parent_visits / visits).sqrt()
+        };
+        let rounds = 20;
+
+        let separate_scan = || {
+            parents
+                .iter()
+                .map(|&parent| {
+                    let log_parent_visits = mcts.visits[parent].ln();
+                    let children = mcts.nodes[parent].children.iter().copied();
+                    children.max_by(|&a, &b| {
+                        score(mcts.wins[a], mcts.visits[a], log_parent_visits)
+                            .partial_cmp(&score(mcts.wins[b], mcts.visits[b], log_parent_visits))
+                            .unwrap()
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        let inline_scan = || {
+            parents
+                .iter()
+                .map(|&parent| {
+                    let log_parent_visits = inline[parent].visits.ln();
+                    let children = inline[parent].children.iter().copied();
+                    children.max_by(|&a, &b| {
+                        score(inline[a].wins, inline[a].visits, log_parent_visits)
+                            .partial_cmp(&score(
+                                inline[b].wins,
+                                inline[b].visits,
+                                log_parent_visits,
+                            ))
+                            .unwrap()
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        let time = |scan: &dyn Fn() -> Vec<Option<NodeIndex>>| {
+            let picks = scan();
+            let start = Instant::now();
+            for _ in 0..rounds {
+                std::hint::black_box(scan());
+            }
+            (picks, start.elapsed() / rounds)
+        };
+        let (separate, separate_time) = time(&separate_scan);
+        let (interleaved, inline_time) = time(&inline_scan);
+
+        println!(
+            "{} nodes, {} parents, per scan: separate arrays {:?}, inline {:?}",
+            mcts.nodes.len(),
+            parents.len(),
+            separate_time,
+            inline_time
+        );
+        assert_eq!(separate, interleaved);
+    }
+
+    #[test]
+    fn pondering_grows_a_tree_that_stays_usable() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.advance_root(4);
+        let stop = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(30));
+                stop.store(true, Ordering::Relaxed);
+            });
+            mcts.ponder(&stop).unwrap();
+        });
+        let pondered = mcts.node_count();
+        assert!(pondered > 10);
+
+        mcts.advance_root(0);
+        assert!(mcts.wins_visits(mcts.root()).1 > 0.0);
+        let action = mcts.get_best_move(100).unwrap();
+        assert!(TicTacToe::from_board("O...X....")
+            .get_legal_moves()
+            .contains(&action));
+    }
+
+    #[test]
+    fn pruning_shrinks_the_tree_but_keeps_the_best_move() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.get_best_move(3000).unwrap();
+        let best = mcts.principal_variation()[0];
+        let before = mcts.node_count();
+        mcts.prune_below_visits(50.0);
+        assert!(mcts.node_count() < before);
+        assert_eq!(mcts.principal_variation()[0], best);
+        for index in 0..mcts.node_count() {
+            for &child in mcts.children_of(index) {
+                assert!(child < mcts.node_count());
+                assert!(mcts.wins_visits(child).1 >= 50.0 || index != mcts.root());
+            }
+        }
+        assert!(mcts.get_best_move(200).is_ok());
+    }
+
+    #[test]
+    fn pruned_moves_are_returned_in_prior_order() {
+        let mut mcts = MCTS::with_puct(Box::new(TicTacToe::new().favouring(8)), 1.0);
+        mcts.load_priors(0);
+        // The favoured cell and another one are barely visited.
+        for visits in [1.0, 1.0, 10.0] {
+            let action = mcts.take_untried_action(0);
+            let mut state = mcts.nodes[0].state.box_clone();
+            state.make_move(action);
+            let index = mcts.push_node(Node::new(state, Some(0), Some(action), 0));
+            mcts.nodes[0].children.push(index);
+            mcts.visits[index] = visits;
+            mcts.visits[0] += visits;
+        }
+        assert_eq!(mcts.nodes[1].last_action, Some(8));
+
+        mcts.prune_below_visits(5.0);
+        assert_eq!(mcts.node_count(), 2);
+        let root = &mcts.nodes[0];
+        let priors = root.priors.as_deref().unwrap();
+        assert_eq!(root.untried_actions.len(), 8);
+        assert_eq!(root.untried_actions.last(), Some(&8));
+        assert!(root
+            .untried_actions
+            .windows(2)
+            .all(|pair| prior_of(priors, pair[0]) <= prior_of(priors, pair[1])));
+    }
+
+    #[test]
+    fn pruned_moves_are_returned_in_move_order() {
+        let mut mcts = MCTS::new(Box::new(Claim::new(8).ordered()));
+        // The two most valuable cells are barely visited.
+        for visits in [1.0, 1.0, 10.0] {
+            let action = mcts.take_untried_action(0);
+            let mut state = mcts.nodes[0].state.box_clone();
+            state.make_move(action);
+            let index = mcts.push_node(Node::new(state, Some(0), Some(action), 0));
+            mcts.nodes[0].children.push(index);
+            mcts.visits[index] = visits;
+            mcts.visits[0] += visits;
+        }
+        assert_eq!(mcts.nodes[1].last_action, Some(7));
+
+        mcts.prune_below_visits(5.0);
+        assert_eq!(mcts.node_count(), 2);
+        assert_eq!(mcts.nodes[0].untried_actions, vec![0, 1, 2, 3, 4, 6, 7]);
+    }
+
+    #[test]
+    fn custom_reward_mapping_is_backed_up() {
+        // A full tree rolls out from its leaves, so every iteration reaches
+        // the drawn position.
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .max_nodes(2)
+            .reward_fn(|result, player| match result {
+                GameResult::Draw => 0.3,
+                GameResult::Win(winner) if winner == player => 1.0,
+                GameResult::Win(_) => 0.0,
+            })
+            .build(Box::new(TicTacToe::from_board("XOXXOOOX.")));
+        for _ in 0..10 {
+            assert!(mcts.run_iteration().unwrap());
+        }
+        let child = mcts.children_of(mcts.root())[0];
+        let (wins, visits) = mcts.wins_visits(child);
+        assert_eq!(visits, 10.0);
+        assert!((wins / visits - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn evaluate_position_scores_won_and_even_positions() {
+        let evaluate = |cells| {
+            let mut mcts = MCTS::with_seed(Box::new(TicTacToe::from_board(cells)), 0);
+            mcts.evaluate_position(2000).unwrap()
+        };
+        assert!(evaluate("XX.OO....") > 0.9);
+        // Every way of filling the middle row is a draw.
+        let mut even = MCTS::with_seed(Box::new(TicTacToe::from_board("XOX...OXO")), 0);
+        assert!((even.evaluate_position(2000).unwrap() - 0.5).abs() < 0.05);
+
+        let mut won = MCTS::new(Box::new(TicTacToe::from_board("XXXOO....")));
+        assert_eq!(won.evaluate_position(10).unwrap(), 0.0);
+        let mut drawn = MCTS::new(Box::new(TicTacToe::from_board("XOXXOOOXX")));
+        assert_eq!(drawn.evaluate_position(10).unwrap(), 0.5);
+    }
+
+    /// Scores every state `0.5` and records the size of each batch and the
+    /// boards it was given.
+    struct RecordingEvaluator {
+        batches: std::sync::Arc<std::sync::Mutex<Vec<Vec<Vec<u8>>>>>,
+    }
+
+    impl LeafEvaluator<usize> for RecordingEvaluator {
+        fn evaluate_batch(&mut self, states: &[&dyn GameState<Action = usize>]) -> Vec<f64> {
+            let boards = states
+                .iter()
+                .map(|state| state.serialize_state().unwrap_or_default())
+                .collect();
+            self.batches.lock().unwrap().push(boards);
+            vec![0.5; states.len()]
+        }
+    }
+
+    #[test]
+    fn leaves_are_evaluated_in_batches_of_distinct_states() {
+        let batches = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let evaluator = RecordingEvaluator {
+            batches: batches.clone(),
+        };
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .leaf_evaluator(Box::new(evaluator), 8)
+            .build(Box::new(TicTacToe::new()));
+        mcts.get_best_move(40).unwrap();
+
+        let batches = batches.lock().unwrap();
+        assert_eq!(batches.len(), 5);
+        for boards in batches.iter() {
+            assert_eq!(boards.len(), 8);
+            // The default virtual loss keeps a batch from repeating a leaf.
+            let mut distinct = boards.clone();
+            distinct.sort();
+            distinct.dedup();
+            assert_eq!(distinct.len(), 8);
+        }
+        assert_eq!(mcts.wins_visits(mcts.root()).1, 40.0);
+    }
+
+    /// The first move ends the game with a winner that does not exist; the
+    /// others lead to positions that only a leaf evaluator can score.
+    #[derive(Clone)]
+    struct BadBranch {
+        picked: Option<usize>,
+    }
+
+    impl GameState for BadBranch {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            match self.picked {
+                None => (0..5).collect(),
+                Some(0) => Vec::new(),
+                Some(_) => vec![0],
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.picked = Some(action);
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.picked == Some(0)
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.is_terminal().then_some(GameResult::Win(7))
+        }
+
+        fn current_player(&self) -> usize {
+            usize::from(self.picked.is_some())
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn failed_batches_leave_no_virtual_loss_behind() {
+        for seed in 0..5 {
+            let batches = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+            let mut mcts = MCTS::builder()
+                .seed(seed)
+                .leaf_evaluator(Box::new(RecordingEvaluator { batches }), 8)
+                .build(Box::new(BadBranch { picked: None }));
+            assert!(mcts.get_best_move(8).is_err());
+            assert!(mcts.visits.iter().all(|&visits| visits == 0.0));
+        }
+    }
+
+    /// A four-ply game that counts the copies made with `box_clone`. With
+    /// `direct` set, `apply` builds children without copying the parent.
+    #[derive(Clone)]
+    struct CopyCounting {
+        plies: usize,
+        last: usize,
+        direct: bool,
+        copies: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl GameState for CopyCounting {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.is_terminal() {
+                Vec::new()
+            } else {
+                vec![0, 1, 2]
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.plies += 1;
+            self.last = action;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.plies == 4
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.is_terminal().then_some(GameResult::Win(self.last % 2))
+        }
+
+        fn current_player(&self) -> usize {
+            self.plies % 2
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            self.copies
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Box::new(self.clone())
+        }
+
+        fn apply(&self, action: usize) -> Box<dyn GameState<Action = usize>> {
+            if !self.direct {
+                let mut state = self.box_clone();
+                state.make_move(action);
+                return state;
+            }
+            Box::new(CopyCounting {
+                plies: self.plies + 1,
+                last: action,
+                direct: true,
+                copies: self.copies.clone(),
+            })
+        }
+    }
+
+    #[test]
+    fn overridden_apply_saves_a_copy_per_child() {
+        let copies = |direct| {
+            let copies = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let state = CopyCounting {
+                plies: 0,
+                last: 0,
+                direct,
+                copies: copies.clone(),
+            };
+            let mut mcts = MCTS::with_seed(Box::new(state), 0);
+            mcts.get_best_move(50).unwrap();
+            (
+                copies.load(std::sync::atomic::Ordering::Relaxed),
+                mcts.node_count(),
+            )
+        };
+        let (cloned, nodes) = copies(false);
+        let (direct, direct_nodes) = copies(true);
+        assert_eq!(nodes, direct_nodes);
+        assert_eq!(cloned - direct, nodes - 1);
+    }
+
+    #[test]
+    fn solver_proves_a_forced_win() {
+        // X must block at 2, which forks the top row and the right column.
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .solver(true)
+            .build(Box::new(TicTacToe::from_board("X...O.O.X")));
+        assert_eq!(mcts.get_best_move(20_000).unwrap(), 2);
+        assert_eq!(mcts.proven_result(), Some(GameResult::Win(0)));
+    }
+
+    #[test]
+    fn proven_move_is_the_one_evaluated() {
+        let mut mcts = hand_built(&[(0, 9.0, 20.0), (4, 3.0, 5.0)]);
+        mcts.set_solver(true);
+        mcts.nodes[2].proven = Some(GameResult::Win(0));
+        mcts.nodes[0].proven = Some(GameResult::Win(0));
+        assert_eq!(mcts.best_action().unwrap(), 4);
+        assert_eq!(mcts.best_child_value(), Some(0.6));
+    }
+
+    #[test]
+    fn draw_value_is_backed_up_for_both_players() {
+        for draw_value in [0.5, 0.25] {
+            // A full tree rolls out from its leaves, so every iteration
+            // reaches the drawn position.
+            let mut mcts = MCTS::builder()
+                .seed(0)
+                .max_nodes(2)
+                .draw_value(draw_value)
+                .build(Box::new(TicTacToe::from_board("XOXXOOOX.")));
+            for _ in 0..10 {
+                assert!(mcts.run_iteration().unwrap());
+            }
+            let child = mcts.children_of(mcts.root())[0];
+            assert_eq!(mcts.wins_visits(child), (10.0 * draw_value, 10.0));
+            assert_eq!(mcts.wins_visits(mcts.root()), (10.0 * draw_value, 10.0));
+        }
+    }
+
+    /// A single move that draws, with the second player compensated for
+    /// moving second.
+    #[derive(Clone)]
+    struct CompensatedDraw {
+        moved: bool,
+    }
+
+    impl GameState for CompensatedDraw {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.moved {
+                Vec::new()
+            } else {
+                vec![0]
+            }
+        }
+
+        fn make_move(&mut self, _action: usize) {
+            self.moved = true;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.moved
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.moved.then_some(GameResult::Draw)
+        }
+
+        fn current_player(&self) -> usize {
+            usize::from(self.moved)
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        fn terminal_reward(&self, _result: GameResult, player: usize) -> f64 {
+            [0.4, 0.6][player]
+        }
+    }
+
+    #[test]
+    fn draws_use_the_terminal_reward_until_a_draw_value_is_set() {
+        let mover_reward = |draw_value: Option<f64>| {
+            let mut mcts = MCTS::builder()
+                .seed(0)
+                .max_nodes(2)
+                .build(Box::new(CompensatedDraw { moved: false }));
+            if let Some(draw_value) = draw_value {
+                mcts.set_draw_value(draw_value);
+            }
+            for _ in 0..4 {
+                assert!(mcts.run_iteration().unwrap());
+            }
+            let (wins, visits) = mcts.wins_visits(1);
+            wins / visits
+        };
+        assert_eq!(mover_reward(None), 0.4);
+        assert_eq!(mover_reward(Some(0.5)), 0.5);
+    }
+
+    #[test]
+    fn counters_track_simulations_and_reused_nodes() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.get_best_move(500).unwrap();
+        assert_eq!(mcts.num_simulations(), 500);
+        assert_eq!(mcts.reuse_count(), 0);
+
+        let kept = mcts.node_count();
+        mcts.advance_root(4);
+        assert!(mcts.reuse_count() > 0);
+        assert!((mcts.reuse_count() as usize) < kept);
+
+        // A solved root ends the search before the budget runs out.
+        let mut solved = MCTS::builder()
+            .seed(0)
+            .solver(true)
+            .build(Box::new(TicTacToe::from_board("XX.OO....")));
+        solved.get_best_move(500).unwrap();
+        assert!(solved.num_simulations() < 500);
+
+        mcts.reset(Box::new(TicTacToe::new()));
+        assert_eq!(mcts.num_simulations(), 0);
+        assert_eq!(mcts.reuse_count(), 0);
+    }
+
+    /// Players take `turns` turns, each choosing a multiplier of `1` or `2`
+    /// and then rolling a die whose face `i` comes up with
+    /// `probabilities[i]` and scores `i + 1` times the multiplier. The higher
+    /// total wins. The game starts with a roll for player `0`.
+    #[derive(Clone)]
+    struct Dice {
+        probabilities: Vec<f64>,
+        turns: usize,
+        multiplier: Option<usize>,
+        totals: [usize; 2],
+        current_player: usize,
+    }
+
+    impl Dice {
+        fn new(probabilities: &[f64], turns: usize) -> Self {
+            Dice {
+                probabilities: probabilities.to_vec(),
+                turns,
+                multiplier: Some(1),
+                totals: [0, 0],
+                current_player: 0,
+            }
+        }
+    }
+
+    impl GameState for Dice {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            match self.multiplier {
+                _ if self.is_terminal() => Vec::new(),
+                Some(_) => (0..self.probabilities.len()).collect(),
+                None => vec![1, 2],
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            match self.multiplier.take() {
+                Some(multiplier) => {
+                    self.totals[self.current_player] += (action + 1) * multiplier;
+                    self.current_player = 1 - self.current_player;
+                    self.turns -= 1;
+                }
+                None => self.multiplier = Some(action),
+            }
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.turns == 0
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            if !self.is_terminal() {
+                return None;
+            }
+            Some(match self.totals[0].cmp(&self.totals[1]) {
+                std::cmp::Ordering::Greater => GameResult::Win(0),
+                std::cmp::Ordering::Less => GameResult::Win(1),
+                std::cmp::Ordering::Equal => GameResult::Draw,
+            })
+        }
+
+        fn current_player(&self) -> usize {
+            self.current_player
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        fn is_chance_node(&self) -> bool {
+            self.multiplier.is_some()
+        }
+
+        fn chance_outcomes(&self) -> Vec<(usize, f64)> {
+            self.probabilities.iter().copied().enumerate().collect()
+        }
+    }
+
+    #[test]
+    fn chance_outcomes_are_sampled_by_probability() {
+        let probabilities = [0.5, 0.3, 0.2];
+        // A full tree rolls out from its leaves, so the search runs the
+        // whole budget.
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .max_nodes(30)
+            .build(Box::new(Dice::new(&probabilities, 3)));
+        mcts.get_best_move(4000).unwrap();
+        assert_eq!(mcts.wins_visits(mcts.root()).1, 4000.0);
+        for (face, visits, _) in child_statistics(&mcts, mcts.root()) {
+            let frequency = visits / 4000.0;
+            assert!(
+                (frequency - probabilities[face]).abs() < 0.03,
+                "face {} came up {}",
+                face,
+                frequency
+            );
+        }
+    }
+
+    #[test]
+    fn chance_nodes_respect_the_node_cap() {
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .max_nodes(20)
+            .build(Box::new(Dice::new(&[1.0 / 6.0; 6], 4)));
+        for _ in 0..50 {
+            mcts.get_best_move(10).unwrap();
+            assert!(mcts.node_count() <= 20, "{} nodes", mcts.node_count());
+        }
+        assert_eq!(mcts.wins_visits(mcts.root()).1, 500.0);
+    }
+
+    #[test]
+    fn root_becomes_fully_expanded() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        assert!(!mcts.is_root_fully_expanded());
+        mcts.get_best_move(8).unwrap();
+        assert!(!mcts.is_root_fully_expanded());
+        mcts.get_best_move(1).unwrap();
+        assert!(mcts.is_root_fully_expanded());
+        assert!(mcts.is_fully_expanded(mcts.root()));
+        assert!(!mcts.is_fully_expanded(mcts.node_count()));
+    }
+
+    /// The opponent in [`Pennies`]. It watches every coin the engine shows
+    /// and answers with a best response to a guess of the next one: the
+    /// frequency of heads so far, or, with `expects_repeat` set, a repeat of
+    /// the last coin.
+    #[derive(Default)]
+    struct PenniesOpponent {
+        expects_repeat: bool,
+        last: usize,
+        heads: f64,
+        plays: f64,
+        score: f64,
+    }
+
+    impl PenniesOpponent {
+        /// The opponent's coin. Heads holds the engine to `(1 - p) / 2`
+        /// and tails to `p`, where `p` is the expected chance of heads.
+        fn answer(&self) -> usize {
+            let p = if self.expects_repeat {
+                if self.last == 0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            } else if self.plays > 0.0 {
+                self.heads / self.plays
+            } else {
+                0.5
+            };
+            usize::from((1.0 - p) / 2.0 >= p)
+        }
+    }
+
+    /// Asymmetric matching pennies against an opponent outside the tree:
+    /// player `0` shows heads (`0`) or tails (`1`) without seeing the
+    /// opponent's coin, and scores `0` for heads against heads, `1` for
+    /// heads against tails, `0.5` for tails against heads and `0` for tails
+    /// against tails. The minimax strategy shows heads a third of the time
+    /// and is worth `1/3` against any opponent.
+    #[derive(Clone)]
+    struct Pennies {
+        shown: Option<usize>,
+        opponent: std::sync::Arc<std::sync::Mutex<PenniesOpponent>>,
+    }
+
+    impl GameState for Pennies {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            match self.shown {
+                None => vec![0, 1],
+                Some(_) => Vec::new(),
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.shown = Some(action);
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.shown.is_some()
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.shown.map(|_| GameResult::Win(0))
+        }
+
+        fn current_player(&self) -> usize {
+            usize::from(self.shown.is_some())
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        // Asked once per player on every visit; player `0`'s call is the
+        // round the opponent answers and remembers.
+        fn terminal_reward(&self, _result: GameResult, player: usize) -> f64 {
+            let shown = self.shown.unwrap();
+            let mut opponent = self.opponent.lock().unwrap();
+            let reward = match (shown, opponent.answer()) {
+                (0, 1) => 1.0,
+                (1, 0) => 0.5,
+                _ => 0.0,
+            };
+            if player == 0 {
+                opponent.last = shown;
+                opponent.heads += if shown == 0 { 1.0 } else { 0.0 };
+                opponent.plays += 1.0;
+                opponent.score += reward;
+                reward
+            } else {
+                1.0 - reward
+            }
+        }
+    }
+
+    /// Plays `rounds` rounds of [`Pennies`], one per iteration, and returns
+    /// how often the engine showed heads and its average score.
+    fn play_pennies(policy: SelectionPolicy, expects_repeat: bool, seed: u64) -> (f64, f64) {
+        let opponent = std::sync::Arc::new(std::sync::Mutex::new(PenniesOpponent {
+            expects_repeat,
+            ..PenniesOpponent::default()
+        }));
+        let state = Pennies {
+            shown: None,
+            opponent: opponent.clone(),
+        };
+        // Both coins are terminal, and a full tree keeps playing rounds from
+        // them.
+        let mut mcts = MCTS::builder()
+            .seed(seed)
+            .selection_policy(policy)
+            .max_nodes(3)
+            .build(Box::new(state));
+        mcts.get_best_move(20_000).unwrap();
+        let opponent = opponent.lock().unwrap();
+        (
+            opponent.heads / opponent.plays,
+            opponent.score / opponent.plays,
+        )
+    }
+
+    #[test]
+    fn exp3_approaches_the_mixed_equilibrium() {
+        for seed in 0..3 {
+            // Against the frequency watcher only the minimax mix of one
+            // third heads avoids being punished.
+            let (heads, _) = play_pennies(SelectionPolicy::Exp3, false, seed);
+            assert!((heads - 1.0 / 3.0).abs() < 0.03, "heads share {}", heads);
+            // A mixed strategy cannot be read from its last coin, so it
+            // keeps the game's value of a third.
+            let (_, score) = play_pennies(SelectionPolicy::Exp3, true, seed);
+            assert!(score > 0.3, "score {}", score);
+        }
+        // UCT's deterministic choices can be read, and the opponent holds
+        // it well below the value a minimax strategy is guaranteed.
+        let (_, score) = play_pennies(SelectionPolicy::Ucb1, true, 0);
+        assert!(score < 0.25, "score {}", score);
+    }
+
+    #[test]
+    fn searching_a_cloned_tree_leaves_the_original_alone() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 4);
+        mcts.get_best_move(2000).unwrap();
+        let before = mcts.move_statistics();
+
+        let mut copy = mcts.clone_tree();
+        assert_eq!(copy.move_statistics(), before);
+        copy.get_best_move(3000).unwrap();
+        copy.advance_root(4);
+        copy.get_best_move(500).unwrap();
+
+        assert_eq!(mcts.move_statistics(), before);
+        assert_eq!(mcts.num_simulations(), 2000);
+        let visits: f64 = before.iter().map(|&(_, visits, _)| visits).sum();
+        assert_eq!(visits, 2000.0);
+    }
+
+    #[test]
+    fn parent_and_child_accumulate_complementary_values() {
+        // Capped at two nodes, every iteration runs through the root and its
+        // one child, whose random rollouts end in wins, losses and draws.
+        let mut mcts = MCTS::builder()
+            .seed(5)
+            .max_nodes(2)
+            .build(Box::new(TicTacToe::new()));
+        mcts.get_best_move(200).unwrap();
+        assert_eq!(mcts.node_count(), 2);
+
+        let root = mcts.root();
+        let child = mcts.children_of(root)[0];
+        let (root_wins, root_visits) = mcts.wins_visits(root);
+        let (child_wins, child_visits) = mcts.wins_visits(child);
+        assert_eq!(root_visits, 200.0);
+        assert_eq!(child_visits, 200.0);
+        assert!(child_wins > 0.0 && child_wins < child_visits);
+        assert_eq!(root_wins + child_wins, 200.0);
+    }
+
+    #[test]
+    fn more_rollouts_per_leaf_steady_the_leaf_value() {
+        // The value the first expanded child records, across seeds.
+        let variance = |rollouts| {
+            let values: Vec<f64> = (0..40)
+                .map(|seed| {
+                    let mut mcts = MCTS::builder()
+                        .seed(seed)
+                        .rollouts_per_leaf(rollouts)
+                        .build(Box::new(TicTacToe::new()));
+                    mcts.get_best_move(1).unwrap();
+                    let child = mcts.children_of(mcts.root())[0];
+                    let (wins, visits) = mcts.wins_visits(child);
+                    // The rollouts are backed up once, as their mean.
+                    assert_eq!(visits, 1.0);
+                    wins
+                })
+                .collect();
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+        };
+        let single = variance(1);
+        let averaged = variance(8);
+        assert!(
+            averaged < single / 2.0,
+            "variance {} with 8 rollouts, {} with 1",
+            averaged,
+            single
+        );
+    }
+
+    #[test]
+    fn grafting_an_explored_subtree_beats_a_cold_start() {
+        // After a corner opening only the centre holds the draw.
+        let mut opened = TicTacToe::new();
+        opened.make_move(0);
+        // The solver ends the book's search once the game is proven a draw.
+        let mut book = MCTS::builder()
+            .seed(0)
+            .solver(true)
+            .build(Box::new(TicTacToe::new()));
+        book.get_best_move(100_000).unwrap();
+
+        let mut warm = MCTS::with_seed(Box::new(opened.clone()), 1);
+        assert!(warm.graft(&book, 0));
+        assert!(warm.node_count() < book.node_count());
+        assert_eq!(warm.best_action().ok(), Some(4));
+
+        let mut cold = MCTS::with_seed(Box::new(opened.clone()), 1);
+        cold.get_best_move(50).unwrap();
+        warm.get_best_move(50).unwrap();
+        assert_ne!(cold.best_action().ok(), Some(4));
+        assert_eq!(warm.best_action().ok(), Some(4));
+
+        // Another position is refused.
+        let mut elsewhere = MCTS::with_seed(Box::new(opened), 1);
+        assert!(!elsewhere.graft(&book, 8));
+        assert_eq!(elsewhere.node_count(), 1);
+    }
+
+    #[test]
+    fn policy_target_is_a_distribution_led_by_the_best_move() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 6);
+        assert!(mcts.policy_target().iter().all(|&(_, share)| share == 0.0));
+        let best = mcts.get_best_move(3000).unwrap();
+
+        let target = mcts.policy_target();
+        assert_eq!(target.len(), 9);
+        let total: f64 = target.iter().map(|&(_, share)| share).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        let (likeliest, _) = target
+            .iter()
+            .copied()
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap();
+        assert_eq!(likeliest, best);
+    }
+
+    #[test]
+    fn minimax_backup_values_a_node_by_its_best_reply() {
+        // As in `each_node_is_credited_with_the_reward_of_its_mover`, but O
+        // always wins through the centre, so X's corner is worth what that
+        // reply leaves X rather than the mean over both replies.
+        let mut mcts = hand_built(&[(0, 0.0, 0.0)]);
+        mcts.set_backup_mode(BackupMode::Minimax);
+        mcts.wins[0] = 0.0;
+        mcts.visits[0] = 0.0;
+        mcts.nodes[1].untried_actions.clear();
+        for action in [4, 1] {
+            let mut state = mcts.nodes[1].state.clone();
+            state.make_move(action);
+            let index = mcts.push_node(Node::new(state, Some(1), Some(action), 1));
+            mcts.nodes[1].children.push(index);
+        }
+
+        for _ in 0..3 {
+            mcts.path = vec![0, 1, 2];
+            mcts.backpropagate(Outcome::Rewards(vec![0.0, 1.0]));
+            mcts.path = vec![0, 1, 3];
+            mcts.backpropagate(Outcome::Rewards(vec![1.0, 0.0]));
+        }
+        assert_eq!(mcts.nodes[2].minimax, 1.0);
+        assert_eq!(mcts.nodes[3].minimax, 0.0);
+        assert_eq!(mcts.nodes[1].minimax, 0.0);
+        assert_eq!(mcts.wins_visits(1), (3.0, 6.0));
+    }
+
+    #[test]
+    fn minimax_backup_finds_a_forced_win_sooner() {
+        // X wins by force with 0, 2 or 3 but only along narrow lines, which
+        // random rollouts below the other moves dilute.
+        let state = TicTacToe::from_board("......X.O");
+        let found = |mode| {
+            (0..40)
+                .filter(|&seed| {
+                    let mut mcts = MCTS::builder()
+                        .seed(seed)
+                        .backup_mode(mode)
+                        .build(Box::new(state.clone()));
+                    [0, 2, 3].contains(&mcts.get_best_move(300).unwrap())
+                })
+                .count()
+        };
+        let average = found(BackupMode::Average);
+        let minimax = found(BackupMode::Minimax);
+        assert!(
+            minimax >= average + 5,
+            "{} of 40 searches found the win with minimax backup, {} with averaging",
+            minimax,
+            average
+        );
+    }
+
+    /// A one-move bandit: player `0` pulls one of the arms, which wins with
+    /// its probability and loses otherwise.
+    #[derive(Clone)]
+    struct Bandit {
+        probabilities: Vec<f64>,
+        arm: Option<usize>,
+        won: Option<bool>,
+    }
+
+    impl Bandit {
+        fn new(probabilities: &[f64]) -> Self {
+            Bandit {
+                probabilities: probabilities.to_vec(),
+                arm: None,
+                won: None,
+            }
+        }
+    }
+
+    impl GameState for Bandit {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            match (self.arm, self.won) {
+                (None, _) => (0..self.probabilities.len()).collect(),
+                (Some(_), None) => vec![0, 1],
+                (Some(_), Some(_)) => Vec::new(),
+            }
+        }
+
+        // After the pull, `1` is a win and `0` a loss.
+        fn make_move(&mut self, action: usize) {
+            match self.arm {
+                None => self.arm = Some(action),
+                Some(_) => self.won = Some(action == 1),
+            }
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.won.is_some()
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.won.map(|won| GameResult::Win(if won { 0 } else { 1 }))
+        }
+
+        fn current_player(&self) -> usize {
+            if self.arm.is_none() {
+                0
+            } else {
+                1
+            }
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+
+        fn is_chance_node(&self) -> bool {
+            self.arm.is_some() && self.won.is_none()
+        }
+
+        fn chance_outcomes(&self) -> Vec<(usize, f64)> {
+            let probability = self.probabilities[self.arm.unwrap()];
+            vec![(1, probability), (0, 1.0 - probability)]
+        }
+    }
+
+    /// Visits of the bandit's first arm after `iterations` iterations under
+    /// `policy`, summed over a few seeds.
+    fn best_arm_visits(policy: SelectionPolicy, probabilities: &[f64], iterations: u32) -> f64 {
+        (0..8)
+            .map(|seed| {
+                let mut mcts = MCTS::builder()
+                    .seed(seed)
+                    .selection_policy(policy)
+                    .build(Box::new(Bandit::new(probabilities)));
+                mcts.run_iterations(iterations).unwrap();
+                mcts.root_action_visits()
+                    .into_iter()
+                    .find(|&(arm, _)| arm == 0)
+                    .map_or(0.0, |(_, visits)| visits)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn ucb1_tuned_settles_on_the_best_arm_sooner() {
+        let arms = [0.9, 0.6, 0.6, 0.6, 0.6];
+        let ucb1 = best_arm_visits(SelectionPolicy::Ucb1, &arms, 400);
+        let tuned = best_arm_visits(SelectionPolicy::Ucb1Tuned, &arms, 400);
+        assert!(tuned > ucb1, "UCB1-Tuned {} UCB1 {}", tuned, ucb1);
+        assert!(tuned > 0.5 * 8.0 * 400.0);
+    }
+
+    #[test]
+    fn confident_search_stops_once_the_best_move_separates() {
+        let mut decided = MCTS::with_seed(Box::new(Bandit::new(&[0.9, 0.1])), 0);
+        assert_eq!(decided.get_best_move_confident(20_000, 1.96).unwrap(), 0);
+        let (_, visits) = decided.wins_visits(decided.root());
+        assert!(visits < 2000.0, "{} iterations", visits);
+
+        let mut tied = MCTS::with_seed(Box::new(Bandit::new(&[0.5, 0.5])), 0);
+        tied.get_best_move_confident(2000, 1.96).unwrap();
+        assert_eq!(tied.wins_visits(tied.root()).1, 2000.0);
+    }
+
+    /// Players add `1` or `2` to a running sum until it reaches `10`;
+    /// player `0` wins on an even total. Every move made and taken back is
+    /// logged as `+1` or `-1`, and every copy of the state is counted.
+    #[derive(Clone)]
+    struct Undoable {
+        history: Vec<usize>,
+        log: std::sync::Arc<std::sync::Mutex<Vec<i32>>>,
+        copies: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Undoable {
+        fn sum(&self) -> usize {
+            self.history.iter().sum()
+        }
+    }
+
+    impl GameState for Undoable {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.is_terminal() {
+                Vec::new()
+            } else {
+                vec![1, 2]
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.history.push(action);
+            self.log.lock().unwrap().push(1);
+        }
+
+        fn undo_move(&mut self, action: usize) -> bool {
+            assert_eq!(
+                self.history.pop(),
+                Some(action),
+                "undid a move out of order"
+            );
+            self.log.lock().unwrap().push(-1);
+            true
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.sum() >= 10
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            self.is_terminal().then(|| GameResult::Win(self.sum() % 2))
+        }
+
+        fn current_player(&self) -> usize {
+            self.history.len() % 2
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            self.copies
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn rollouts_undo_every_move_they_make() {
+        // The log of `rollouts` rollouts from the root, and the number of
+        // copies they took.
+        let run = |rollouts| {
+            let state = Undoable {
+                history: Vec::new(),
+                log: Default::default(),
+                copies: Default::default(),
+            };
+            let mut mcts = MCTS::builder()
+                .seed(0)
+                .rollouts_per_leaf(rollouts)
+                .build(state.box_clone());
+            state.copies.store(0, std::sync::atomic::Ordering::Relaxed);
+            mcts.path = vec![mcts.root];
+            mcts.simulate(mcts.root).unwrap();
+            let log = state.log.lock().unwrap().clone();
+            (log, state.copies.load(std::sync::atomic::Ordering::Relaxed))
+        };
+
+        let (log, copies) = run(8);
+        let mut depth = 0;
+        let mut unwound = 0;
+        for (i, &step) in log.iter().enumerate() {
+            depth += step;
+            assert!(depth >= 0);
+            if depth == 0 && i > 0 {
+                unwound += 1;
+            }
+        }
+        // All but the last rollout are taken back to the leaf.
+        assert_eq!(unwound, 7);
+        assert!(depth >= 5);
+        assert_eq!(run(2).1, copies);
+    }
+
+    #[cfg(feature = "tracing")]
+    mod tracing_events {
+        use super::*;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        /// Records the message and `node` field of every event, and ignores
+        /// spans.
+        struct EventLog(Arc<Mutex<Vec<TracedEvent>>>);
+
+        #[derive(Debug, Default, PartialEq)]
+        struct TracedEvent {
+            message: String,
+            node: Option<u64>,
+        }
+
+        impl Visit for TracedEvent {
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                if field.name() == "node" {
+                    self.node = Some(value);
+                }
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    self.message = format!("{:?}", value);
+                }
+            }
+        }
+
+        impl Subscriber for EventLog {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _span: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut traced = TracedEvent::default();
+                event.record(&mut traced);
+                self.0.lock().unwrap().push(traced);
+            }
+
+            fn enter(&self, _span: &Id) {}
+
+            fn exit(&self, _span: &Id) {}
+        }
+
+        #[test]
+        fn an_iteration_traces_each_phase_in_order() {
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+            tracing::subscriber::with_default(EventLog(events.clone()), || {
+                assert!(mcts.run_iteration().unwrap());
+            });
+
+            let events = events.lock().unwrap();
+            let expected = [
+                ("select", 0),
+                ("expand", 1),
+                ("simulate", 1),
+                ("backpropagate", 1),
+                ("backpropagate", 0),
+            ];
+            let expected: Vec<TracedEvent> = expected
+                .iter()
+                .map(|&(message, node)| TracedEvent {
+                    message: message.to_string(),
+                    node: Some(node),
+                })
+                .collect();
+            assert_eq!(*events, expected);
+        }
+    }
+
+    #[test]
+    fn nan_values_are_never_selected() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 7);
+        mcts.get_best_move(300).unwrap();
+        let poisoned = mcts.children_of(mcts.root())[0];
+        mcts.wins[poisoned] = f64::NAN;
+        let (_, visits) = mcts.wins_visits(poisoned);
+
+        let best = mcts.get_best_move(300).unwrap();
+        assert_eq!(mcts.wins_visits(poisoned).1, visits);
+        assert_ne!(Some(best), mcts.last_action_of(poisoned));
+    }
+
+    #[test]
+    fn visit_grid_covers_the_root_children() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 8);
+        mcts.get_best_move(500).unwrap();
+        let grid = mcts.to_visit_grid(3, 3);
+        assert_eq!(grid.len(), 3);
+        assert!(grid.iter().all(|row| row.len() == 3));
+
+        let children: f64 = mcts
+            .children_of(mcts.root())
+            .iter()
+            .map(|&child| mcts.wins_visits(child).1)
+            .sum();
+        let cells: f64 = grid.iter().flatten().sum();
+        assert_eq!(cells, children);
+        for (action, visits) in mcts.root_action_visits() {
+            assert_eq!(grid[action / 3][action % 3], visits);
+        }
+    }
+
+    #[test]
+    fn move_ordering_expands_the_strongest_move_first() {
+        for seed in 0..5 {
+            let mut mcts = MCTS::with_seed(Box::new(Claim::new(8).ordered()), seed);
+            for _ in 0..3 {
+                assert!(mcts.run_iteration().unwrap());
+            }
+            let expanded: Vec<usize> = mcts
+                .children_of(mcts.root())
+                .iter()
+                .map(|&child| mcts.last_action_of(child).unwrap())
+                .collect();
+            assert_eq!(expanded, vec![7, 6, 5]);
+        }
+    }
+
+    #[test]
+    fn best_moves_are_ranked_by_visits() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 9);
+        let best = mcts.get_best_move(2000).unwrap();
+        let top = mcts.best_moves(3);
+        assert_eq!(top.len(), 3);
+        assert_eq!(top[0].0, best);
+
+        let visits_of = |action| {
+            let visits = mcts.root_action_visits();
+            visits.iter().find(|&&(a, _)| a == action).unwrap().1
+        };
+        assert!(top
+            .windows(2)
+            .all(|pair| visits_of(pair[0].0) >= visits_of(pair[1].0)));
+        assert_eq!(mcts.best_moves(20).len(), 9);
+    }
+
+    #[test]
+    fn simulating_a_won_state_returns_its_reward() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        // X has completed the top row, and O is to move.
+        let won = TicTacToe::from_board("XXXOO....");
+        assert!(won.is_terminal());
+        assert_eq!(mcts.simulate_state(&won).unwrap(), 0.0);
+        assert_eq!(mcts.node_count(), 1);
+    }
+
+    #[test]
+    fn uct_values_are_pinned_on_a_small_tree() {
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .max_nodes(3)
+            .build(Box::new(TicTacToe::new()));
+        for _ in 0..2 {
+            assert!(mcts.run_iteration().unwrap());
+        }
+        let root = mcts.root();
+        let (first, second) = match mcts.children_of(root)[..] {
+            [first, second] => (first, second),
+            _ => unreachable!(),
+        };
+        mcts.wins[root] = 4.0;
+        mcts.visits[root] = 10.0;
+        (mcts.wins[first], mcts.visits[first]) = (3.0, 4.0);
+        (mcts.wins[second], mcts.visits[second]) = (2.0, 5.0);
+
+        // 3/4 + sqrt(2) * sqrt(ln(10) / 4), and ln(9) for the child sum.
+        assert_eq!(mcts.policy_value(root, first), 1.8229830131446738);
+        mcts.set_parent_visits(ParentVisits::ChildSum);
+        assert_eq!(mcts.policy_value(root, first), 1.798147073968205);
+    }
+
+    #[test]
+    fn parent_visits_match_the_documented_child_sums() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 10);
+        mcts.get_best_move(500).unwrap();
+        let child_sum = |node: NodeIndex| -> f64 {
+            mcts.children_of(node)
+                .iter()
+                .map(|&child| mcts.wins_visits(child).1)
+                .sum()
+        };
+        assert_eq!(mcts.wins_visits(mcts.root()).1, child_sum(mcts.root()));
+        for node in 1..mcts.node_count() {
+            if !mcts.children_of(node).is_empty() {
+                assert_eq!(mcts.wins_visits(node).1, child_sum(node) + 1.0);
+            }
+        }
+    }
+
+    /// Hand-built TicTacToe nodes, one per `(parent, action)`, with the
+    /// root first. Nodes that get children have no untried moves left.
+    fn hand_built_nodes(edges: &[(NodeIndex, usize)]) -> Vec<Node<usize>> {
+        let root = TicTacToe::new();
+        let mut nodes = vec![Node::new(root.box_clone(), None, None, 1)];
+        for &(parent, action) in edges {
+            let state = nodes[parent].state.apply(action);
+            let mover = nodes[parent].state.current_player();
+            let index = nodes.len();
+            nodes.push(Node::new(state, Some(parent), Some(action), mover));
+            nodes[parent].children.push(index);
+            nodes[parent].untried_actions.clear();
+        }
+        nodes
+    }
+
+    #[test]
+    fn selection_follows_the_best_values_down_a_hand_built_tree() {
+        // The centre beats the corner at the root, and below it the far
+        // corner beats the edge.
+        let nodes = hand_built_nodes(&[(0, 0), (0, 4), (2, 1), (2, 8)]);
+        let wins = vec![9.0, 5.0, 6.0, 2.0, 4.0];
+        let visits = vec![21.0, 10.0, 11.0, 5.0, 5.0];
+        let mut mcts = MCTS::from_nodes(nodes, wins, visits, 0).unwrap();
+        mcts.exploration_constant = 0.0;
+        assert_eq!(mcts.select(0), 4);
+        assert_eq!(mcts.path, vec![0, 2, 4]);
+
+        // With a large exploration constant the less visited corner wins at
+        // the root, and it is a leaf with moves left to expand.
+        mcts.exploration_constant = 10.0;
+        assert_eq!(mcts.select(0), 1);
+        assert_eq!(mcts.path, vec![0, 1]);
+    }
+
+    #[test]
+    fn from_nodes_rejects_malformed_trees() {
+        let statistics = || (vec![0.0; 3], vec![1.0; 3]);
+
+        // The grandchild also lists its own parent as a child.
+        let mut nodes = hand_built_nodes(&[(0, 0), (1, 4)]);
+        nodes[2].children.push(1);
+        let (wins, visits) = statistics();
+        assert_eq!(
+            MCTS::from_nodes(nodes, wins, visits, 0).err(),
+            Some("children form a cycle")
+        );
+
+        // A node that names a later node as its parent.
+        let mut nodes = hand_built_nodes(&[(0, 0), (0, 4)]);
+        nodes[1].parent = Some(2);
+        nodes[0].children.retain(|&child| child != 1);
+        nodes[2].children.push(1);
+        let (wins, visits) = statistics();
+        assert_eq!(
+            MCTS::from_nodes(nodes, wins, visits, 0).err(),
+            Some("node comes before its parent")
+        );
+
+        let (wins, visits) = statistics();
+        assert!(MCTS::from_nodes(hand_built_nodes(&[(0, 0), (1, 4)]), wins, visits, 0).is_ok());
+    }
+
+    #[test]
+    fn restricting_the_root_to_one_move_searches_only_it() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 11);
+        mcts.get_best_move(200).unwrap();
+        assert!(!mcts.restrict_root_moves(&[3, 9]));
+        assert!(mcts.restrict_root_moves(&[3]));
+        assert_eq!(mcts.get_best_move(500).unwrap(), 3);
+        let moves: Vec<usize> = mcts
+            .root_action_visits()
+            .into_iter()
+            .map(|(action, _)| action)
+            .collect();
+        assert_eq!(moves, vec![3]);
+    }
+
+    #[test]
+    fn split_searches_grow_the_same_tree() {
+        let mut split = MCTS::with_seed(Box::new(TicTacToe::new()), 12);
+        split.run_iterations(500).unwrap();
+        split.run_iterations(500).unwrap();
+        let mut whole = MCTS::with_seed(Box::new(TicTacToe::new()), 12);
+        whole.run_iterations(1000).unwrap();
+
+        assert_eq!(split.node_count(), whole.node_count());
+        assert_eq!(split.wins, whole.wins);
+        assert_eq!(split.visits, whole.visits);
+        assert_eq!(split.to_dot(usize::MAX), whole.to_dot(usize::MAX));
+        assert_eq!(split.best_move(), whole.best_move());
+    }
+
+    const CORNERS: [usize; 4] = [0, 2, 6, 8];
+
+    /// Writes down, for every move it picks, the move and whether a corner
+    /// was free. Takes the first free corner when `corner_first`, and a
+    /// random move otherwise.
+    struct CornerLog {
+        corner_first: bool,
+        moves: std::sync::Arc<std::sync::Mutex<Vec<(usize, bool)>>>,
+    }
+
+    impl RolloutPolicy<usize> for CornerLog {
+        fn choose(
+            &mut self,
+            _state: &dyn GameState<Action = usize>,
+            moves: &[usize],
+            rng: &mut dyn RngCore,
+        ) -> usize {
+            let corner = moves.iter().copied().find(|cell| CORNERS.contains(cell));
+            let choice = match corner {
+                Some(corner) if self.corner_first => corner,
+                _ => moves[rng.gen_range(0..moves.len())],
+            };
+            self.moves.lock().unwrap().push((choice, corner.is_some()));
+            choice
+        }
+    }
+
+    #[test]
+    fn per_player_rollouts_follow_the_opponent_model() {
+        let x_moves = std::sync::Arc::default();
+        let o_moves = std::sync::Arc::default();
+        let policy = PerPlayerRollout::new(vec![
+            Box::new(CornerLog {
+                corner_first: false,
+                moves: std::sync::Arc::clone(&x_moves),
+            }),
+            Box::new(CornerLog {
+                corner_first: true,
+                moves: std::sync::Arc::clone(&o_moves),
+            }),
+        ]);
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .rollout_policy(Box::new(policy))
+            .build(Box::new(TicTacToe::new()));
+        for _ in 0..200 {
+            mcts.simulate_state(&TicTacToe::new()).unwrap();
+        }
+
+        // O takes a corner whenever one is free, X plays all over the board.
+        let o_moves = o_moves.lock().unwrap();
+        assert!(!o_moves.is_empty());
+        assert!(o_moves
+            .iter()
+            .all(|&(cell, corner_free)| !corner_free || CORNERS.contains(&cell)));
+        let x_moves = x_moves.lock().unwrap();
+        let x_edges = x_moves
+            .iter()
+            .filter(|&&(cell, corner_free)| corner_free && !CORNERS.contains(&cell))
+            .count();
+        assert!(x_edges > x_moves.len() / 4);
+    }
+
+    /// A long game where either player may instead end it at once in a draw
+    /// with move `0`, leaving terminal leaves next to every node on the
+    /// long line.
+    #[derive(Clone)]
+    struct Shortcut {
+        remaining: usize,
+        ended: bool,
+        current_player: usize,
+    }
+
+    impl GameState for Shortcut {
+        type Action = usize;
+
+        fn get_legal_moves(&self) -> Vec<usize> {
+            if self.is_terminal() {
+                Vec::new()
+            } else {
+                vec![0, 1]
+            }
+        }
+
+        fn make_move(&mut self, action: usize) {
+            self.ended = action == 0;
+            self.remaining -= 1;
+            self.current_player = 1 - self.current_player;
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.ended || self.remaining == 0
+        }
+
+        fn get_winner(&self) -> Option<GameResult> {
+            match (self.ended, self.remaining) {
+                (true, _) => Some(GameResult::Draw),
+                (false, 0) => Some(GameResult::Win(1 - self.current_player)),
+                _ => None,
+            }
+        }
+
+        fn current_player(&self) -> usize {
+            self.current_player
+        }
+
+        fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn selecting_a_terminal_leaf_does_not_stop_the_search() {
+        let game = Shortcut {
+            remaining: 1000,
+            ended: false,
+            current_player: 0,
+        };
+        let mut mcts = MCTS::with_seed(Box::new(game), 0);
+        let (_, report) = mcts.get_best_move_report(500).unwrap();
+        assert_eq!(report.iterations, 500);
+        // Terminal leaves are selected again and again, but the long line
+        // still grows between them.
+        assert!(report.nodes_created > 10);
+        assert!(mcts.tree_depth() > 5);
+    }
+
+    #[test]
+    fn branching_factor_shrinks_towards_the_end_of_the_game() {
+        let fresh = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        assert_eq!(fresh.average_branching_factor(), 0.0);
+        assert_eq!(fresh.average_depth(), 0.0);
+
+        let shape = |board| {
+            let mut mcts = MCTS::with_seed(Box::new(TicTacToe::from_board(board)), 0);
+            mcts.run_iterations(2000).unwrap();
+            (mcts.average_branching_factor(), mcts.average_depth())
+        };
+        let (opening, opening_depth) = shape(".........");
+        let (middle, _) = shape("X...O....");
+        let (ending, _) = shape("XO.XO.O.X");
+        assert!(opening < 9.0);
+        assert!(opening_depth > 1.0);
+        assert!(opening > middle);
+        assert!(middle > ending);
+    }
+
+    #[test]
+    fn both_reward_scales_pick_the_same_move() {
+        let search = |scale, draw_value| {
+            let mut mcts = MCTS::builder()
+                .seed(0)
+                .reward_scale(scale)
+                .draw_value(draw_value)
+                .build(Box::new(TicTacToe::from_board("X.O......")));
+            let best = mcts.get_best_move(2000).unwrap();
+            (best, child_statistics(&mcts, mcts.root()))
+        };
+        let zero_one = search(RewardScale::ZeroOne, 0.5);
+        let neg_one_one = search(RewardScale::NegOneOne, 0.0);
+        assert_eq!(zero_one.0, neg_one_one.0);
+        assert_eq!(zero_one.1, neg_one_one.1);
+
+        // Values come out on the scale: O has lost the finished game.
+        let mut mcts = MCTS::builder()
+            .seed(0)
+            .reward_scale(RewardScale::NegOneOne)
+            .build(Box::new(TicTacToe::new()));
+        let lost = TicTacToe::from_board("XXXOO....");
+        assert_eq!(mcts.simulate_state(&lost).unwrap(), -1.0);
+    }
+
+    #[test]
+    fn playing_a_sequence_moves_the_root_to_its_position() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::new()), 0);
+        mcts.run_iterations(200).unwrap();
+        mcts.play_sequence(&[0, 4, 8]).unwrap();
+        let expected = TicTacToe::from_board("X...O...X");
+        assert_eq!(
+            mcts.nodes[mcts.root()].state.serialize_state(),
+            expected.serialize_state()
+        );
+        assert_eq!(mcts.nodes[mcts.root()].state.current_player(), 1);
+
+        // The second move takes a cell already held, so nothing is played.
+        assert_eq!(
+            mcts.play_sequence(&[1, 4, 2]),
+            Err(MctsError::IllegalMove(1))
+        );
+        assert_eq!(
+            mcts.nodes[mcts.root()].state.serialize_state(),
+            expected.serialize_state()
+        );
+        assert!(mcts.get_best_move(500).is_ok());
+    }
+
+    #[test]
+    fn selection_fn_replaces_the_selection_formula() {
+        let children = [(0, 9.0, 10.0), (1, 1.0, 2.0), (2, 3.0, 5.0)];
+
+        // UCB1 explores the barely visited child.
+        let mut mcts = hand_built(&children);
+        let selected = mcts.select(0);
+        assert_eq!(mcts.nodes[selected].last_action, Some(1));
+        let mut mcts = hand_built(&children);
+        mcts.set_selection_fn(|child, parent| {
+            child.wins / child.visits + (2.0 * parent.visits.ln() / child.visits).sqrt()
+        });
+        let selected = mcts.select(0);
+        assert_eq!(mcts.nodes[selected].last_action, Some(1));
+
+        // A greedy closure takes the best win rate instead.
+        let mut mcts = hand_built(&children);
+        mcts.set_selection_fn(|child, _| child.wins / child.visits);
+        let selected = mcts.select(0);
+        assert_eq!(mcts.nodes[selected].last_action, Some(0));
+    }
+
+    #[test]
+    fn best_child_state_shows_the_best_move_played() {
+        let mut mcts = MCTS::with_seed(Box::new(TicTacToe::from_board("XX.OO....")), 0);
+        assert!(mcts.best_child_state().is_none());
+
+        let best = mcts.get_best_move(500).unwrap();
+        assert_eq!(best, 2);
+        let expected = TicTacToe::from_board("XXXOO....");
+        let state = mcts.best_child_state().unwrap();
+        assert_eq!(state.serialize_state(), expected.serialize_state());
+        assert!(state.is_terminal());
+    }
+}
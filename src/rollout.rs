@@ -0,0 +1,106 @@
+use crate::mcts::{GameResult, GameState};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use rand::{Rng, RngCore};
+
+/// Chooses the moves played during a rollout.
+///
+/// `rng` is the engine's own RNG, so policies that need randomness stay
+/// reproducible under [`MCTS::with_seed`](crate::mcts::MCTS::with_seed).
+/// Policies must be [`Send`], like the engine that owns them.
+pub trait RolloutPolicy<A>: Send {
+    fn choose(
+        &mut self,
+        state: &dyn GameState<Action = A>,
+        moves: &[A],
+        rng: &mut dyn RngCore,
+    ) -> A;
+}
+
+/// Picks a uniformly random legal move. This is the default policy.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomRollout;
+
+impl<A: Copy> RolloutPolicy<A> for RandomRollout {
+    fn choose(
+        &mut self,
+        _state: &dyn GameState<Action = A>,
+        moves: &[A],
+        rng: &mut dyn RngCore,
+    ) -> A {
+        moves[rng.gen_range(0..moves.len())]
+    }
+}
+
+/// Plays a move that wins on the spot when there is one, and a random legal
+/// move otherwise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GreedyRollout;
+
+impl<A: Copy + Eq> RolloutPolicy<A> for GreedyRollout {
+    fn choose(
+        &mut self,
+        state: &dyn GameState<Action = A>,
+        moves: &[A],
+        rng: &mut dyn RngCore,
+    ) -> A {
+        let mover = state.current_player();
+        for &action in moves {
+            let mut next = state.box_clone();
+            next.make_move(action);
+            if next.is_terminal() && next.get_winner() == Some(GameResult::Win(mover)) {
+                return action;
+            }
+        }
+        moves[rng.gen_range(0..moves.len())]
+    }
+}
+
+/// Plays the move reported by [`GameState::winning_move`] when there is one,
+/// then the one reported by [`GameState::must_block`], and a random legal
+/// move otherwise. Plays like [`RandomRollout`] for games that implement
+/// neither method.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DecisiveRollout;
+
+impl<A: Copy + Eq> RolloutPolicy<A> for DecisiveRollout {
+    fn choose(
+        &mut self,
+        state: &dyn GameState<Action = A>,
+        moves: &[A],
+        rng: &mut dyn RngCore,
+    ) -> A {
+        state
+            .winning_move()
+            .or_else(|| state.must_block())
+            .unwrap_or_else(|| moves[rng.gen_range(0..moves.len())])
+    }
+}
+
+/// Lets every player follow their own rollout policy, for example to model
+/// a known opponent rather than assume random play from both sides. The
+/// policy at index `p` chooses the moves of player `p`, and players without
+/// a policy play like [`RandomRollout`].
+pub struct PerPlayerRollout<A> {
+    policies: Vec<Box<dyn RolloutPolicy<A>>>,
+}
+
+impl<A> PerPlayerRollout<A> {
+    pub fn new(policies: Vec<Box<dyn RolloutPolicy<A>>>) -> Self {
+        PerPlayerRollout { policies }
+    }
+}
+
+impl<A: Copy + Eq> RolloutPolicy<A> for PerPlayerRollout<A> {
+    fn choose(
+        &mut self,
+        state: &dyn GameState<Action = A>,
+        moves: &[A],
+        rng: &mut dyn RngCore,
+    ) -> A {
+        match self.policies.get_mut(state.current_player()) {
+            Some(policy) => policy.choose(state, moves, rng),
+            None => moves[rng.gen_range(0..moves.len())],
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Counts heap allocations during rollouts, to check that games overriding
+//! `GameState::legal_moves_into` do not allocate on every move.
+
+use mcts_rs::mcts::{GameResult, GameState, MCTS};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Passes every request on to the system allocator and counts them.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Both players add one of `width` numbers to a running sum for `plies`
+/// plies; player `0` wins if the sum ends up even. With `buffered` set the
+/// moves are written straight into the rollout buffer.
+#[derive(Clone)]
+struct Wide {
+    width: usize,
+    plies_left: usize,
+    sum: usize,
+    buffered: bool,
+}
+
+impl GameState for Wide {
+    type Action = usize;
+
+    fn get_legal_moves(&self) -> Vec<usize> {
+        if self.is_terminal() {
+            Vec::new()
+        } else {
+            (0..self.width).collect()
+        }
+    }
+
+    fn legal_moves_into(&self, buf: &mut Vec<usize>) {
+        if !self.buffered {
+            buf.extend(self.get_legal_moves());
+        } else if !self.is_terminal() {
+            buf.extend(0..self.width);
+        }
+    }
+
+    fn make_move(&mut self, action: usize) {
+        self.sum += action;
+        self.plies_left -= 1;
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.plies_left == 0
+    }
+
+    fn get_winner(&self) -> Option<GameResult> {
+        self.is_terminal().then_some(GameResult::Win(self.sum % 2))
+    }
+
+    fn current_player(&self) -> usize {
+        self.plies_left % 2
+    }
+
+    fn box_clone(&self) -> Box<dyn GameState<Action = usize>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Allocations per iteration of a search rolling out 50 plies of a
+/// 200-move game. The tree is capped at the root and one child, so every
+/// iteration after the first is a rollout from that child.
+fn allocations_per_rollout(buffered: bool) -> f64 {
+    let state = Wide {
+        width: 200,
+        plies_left: 51,
+        sum: 0,
+        buffered,
+    };
+    let mut mcts = MCTS::builder().seed(0).max_nodes(2).build(Box::new(state));
+    // Let the engine's buffers grow to size first.
+    mcts.get_best_move(10).unwrap();
+
+    let rollouts = 1000;
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    mcts.get_best_move(rollouts).unwrap();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    allocations as f64 / rollouts as f64
+}
+
+#[test]
+fn reused_move_buffer_avoids_per_move_allocations() {
+    let unbuffered = allocations_per_rollout(false);
+    let buffered = allocations_per_rollout(true);
+    assert!(unbuffered >= 50.0, "{} allocations per rollout", unbuffered);
+    assert!(buffered < 5.0, "{} allocations per rollout", buffered);
+}
@@ -0,0 +1,84 @@
+//! Checks the engine against the known optimal strategy for Nim: a position
+//! is lost for the player to move exactly when the XOR of its heap sizes is
+//! zero, so from any other position the winning moves are the ones that
+//! leave a zero XOR.
+
+use mcts_rs::mcts::{GameResult, GameState, MCTS};
+
+/// Multi-heap Nim, as in `src/bin/nim.rs`: a move takes any positive number
+/// of objects from one heap, and whoever takes the last object wins.
+#[derive(Clone)]
+struct Nim {
+    heaps: Vec<usize>,
+    current_player: usize,
+}
+
+impl GameState for Nim {
+    // Moves are `(heap, count)`: take `count` objects from `heap`.
+    type Action = (usize, usize);
+
+    fn get_legal_moves(&self) -> Vec<(usize, usize)> {
+        self.heaps
+            .iter()
+            .enumerate()
+            .flat_map(|(heap, &size)| (1..=size).map(move |count| (heap, count)))
+            .collect()
+    }
+
+    fn make_move(&mut self, (heap, count): (usize, usize)) {
+        self.heaps[heap] -= count;
+        self.current_player = 1 - self.current_player;
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.heaps.iter().all(|&size| size == 0)
+    }
+
+    fn get_winner(&self) -> Option<GameResult> {
+        if self.is_terminal() {
+            Some(GameResult::Win(1 - self.current_player))
+        } else {
+            None
+        }
+    }
+
+    fn current_player(&self) -> usize {
+        self.current_player
+    }
+
+    fn box_clone(&self) -> Box<dyn GameState<Action = (usize, usize)>> {
+        Box::new(self.clone())
+    }
+}
+
+fn nim_sum(heaps: &[usize]) -> usize {
+    heaps.iter().fold(0, |sum, &size| sum ^ size)
+}
+
+#[test]
+fn engine_leaves_a_zero_nim_sum_from_winning_positions() {
+    let positions: [&[usize]; 5] = [&[1, 2], &[3, 1], &[1, 2, 4], &[2, 3, 4], &[1, 3, 5]];
+    for heaps in positions {
+        assert_ne!(nim_sum(heaps), 0);
+        for seed in 0..3 {
+            let nim = Nim {
+                heaps: heaps.to_vec(),
+                current_player: 0,
+            };
+            let mut mcts = MCTS::with_seed(Box::new(nim), seed);
+            let (heap, count) = mcts.get_best_move(20_000).unwrap();
+
+            let mut after = heaps.to_vec();
+            after[heap] -= count;
+            assert_eq!(
+                nim_sum(&after),
+                0,
+                "from {:?} with seed {} the engine took {} from heap {}",
+                heaps,
+                seed,
+                count,
+                heap
+            );
+        }
+    }
+}